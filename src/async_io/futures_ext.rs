@@ -0,0 +1,140 @@
+//! `WaterBuffer` helpers for futures-io's `AsyncRead` / `AsyncWrite`.
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+use super::DEFAULT_READ_RESERVE;
+use crate::WaterBuffer;
+
+/// Reads once from `reader` into the spare capacity of `buffer`.
+/// futures-io only accepts initialized slices, so spare bytes that were never initialized
+/// are zeroed the first time they are handed out, later polls reuse them as they are.
+/// Reserves [`DEFAULT_READ_RESERVE`] bytes first when there is no room left,
+/// returns the number of bytes read, `0` meaning EOF.
+pub fn poll_read_water<R: AsyncRead + ?Sized>(
+    reader: Pin<&mut R>,
+    cx: &mut Context<'_>,
+    buffer: &mut WaterBuffer<u8>,
+) -> Poll<io::Result<usize>> {
+    if buffer.mut_len() == 0 {
+        buffer.reserve(DEFAULT_READ_RESERVE);
+    }
+    let n = ready!(reader.poll_read(cx, buffer.init_spare_mut(usize::MAX)))?;
+    assert!(n <= buffer.mut_len(), "reader returned more bytes than it was given");
+    buffer.advance_mut(n);
+    Poll::Ready(Ok(n))
+}
+
+/// Writes once from the readable window of `buffer` and advances past the written bytes
+pub fn poll_write_water<W: AsyncWrite + ?Sized>(
+    writer: Pin<&mut W>,
+    cx: &mut Context<'_>,
+    buffer: &mut WaterBuffer<u8>,
+) -> Poll<io::Result<usize>> {
+    if buffer.is_empty() {
+        return Poll::Ready(Ok(0));
+    }
+    let n = ready!(writer.poll_write(cx, &buffer[..]))?;
+    buffer.advance(n);
+    Poll::Ready(Ok(n))
+}
+
+/// Writes the whole readable window of `buffer`, advancing past every partial write
+pub fn poll_write_all_water<W: AsyncWrite + ?Sized>(
+    mut writer: Pin<&mut W>,
+    cx: &mut Context<'_>,
+    buffer: &mut WaterBuffer<u8>,
+) -> Poll<io::Result<()>> {
+    while !buffer.is_empty() {
+        if ready!(poll_write_water(writer.as_mut(), cx, buffer))? == 0 {
+            return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+        }
+    }
+    Poll::Ready(Ok(()))
+}
+
+/// Reading into a `WaterBuffer` from a futures-io `AsyncRead`
+pub trait WaterReadExt: AsyncRead {
+    /// Reads once into the spare capacity of `buffer`, see [`poll_read_water`]
+    fn read_buf_water<'a>(&'a mut self, buffer: &'a mut WaterBuffer<u8>) -> ReadWater<'a, Self>
+    where
+        Self: Unpin,
+    {
+        ReadWater { reader: self, buffer }
+    }
+}
+
+impl<R: AsyncRead + ?Sized> WaterReadExt for R {}
+
+/// Writing a `WaterBuffer` to a futures-io `AsyncWrite`
+pub trait WaterWriteExt: AsyncWrite {
+    /// Writes once from `buffer` and advances past the written bytes
+    fn write_water<'a>(&'a mut self, buffer: &'a mut WaterBuffer<u8>) -> WriteWater<'a, Self>
+    where
+        Self: Unpin,
+    {
+        WriteWater { writer: self, buffer }
+    }
+
+    /// Writes the whole readable window of `buffer`, leaving it empty on success
+    fn write_all_water<'a>(&'a mut self, buffer: &'a mut WaterBuffer<u8>) -> WriteAllWater<'a, Self>
+    where
+        Self: Unpin,
+    {
+        WriteAllWater { writer: self, buffer }
+    }
+}
+
+impl<W: AsyncWrite + ?Sized> WaterWriteExt for W {}
+
+/// Future returned by [`WaterReadExt::read_buf_water`]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ReadWater<'a, R: ?Sized> {
+    reader: &'a mut R,
+    buffer: &'a mut WaterBuffer<u8>,
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> Future for ReadWater<'_, R> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        poll_read_water(Pin::new(&mut *this.reader), cx, this.buffer)
+    }
+}
+
+/// Future returned by [`WaterWriteExt::write_water`]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WriteWater<'a, W: ?Sized> {
+    writer: &'a mut W,
+    buffer: &'a mut WaterBuffer<u8>,
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized> Future for WriteWater<'_, W> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        poll_write_water(Pin::new(&mut *this.writer), cx, this.buffer)
+    }
+}
+
+/// Future returned by [`WaterWriteExt::write_all_water`]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WriteAllWater<'a, W: ?Sized> {
+    writer: &'a mut W,
+    buffer: &'a mut WaterBuffer<u8>,
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized> Future for WriteAllWater<'_, W> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        poll_write_all_water(Pin::new(&mut *this.writer), cx, this.buffer)
+    }
+}
@@ -0,0 +1,16 @@
+//! Extension traits reading into and writing from `WaterBuffer` with async IO traits.
+//!
+//! Reads go straight into the spare capacity after the readable window and are
+//! committed with `advance_mut`, writes consume the readable window with `advance`,
+//! so no glue code or temporary buffers are needed.
+//!
+//! * [`tokio_ext`] for `tokio::io::AsyncRead` / `AsyncWrite` (`tokio` feature)
+//! * [`futures_ext`] for `futures::io::AsyncRead` / `AsyncWrite` (`futures-io` feature)
+
+#[cfg(feature = "futures-io")]
+pub mod futures_ext;
+#[cfg(feature = "tokio")]
+pub mod tokio_ext;
+
+/// spare capacity reserved before a read when the buffer has no room left
+pub const DEFAULT_READ_RESERVE: usize = 8 * 1024;
@@ -0,0 +1,234 @@
+//! Vectored IO over a list of buffers.
+//!
+//! A response is usually a header buffer followed by a body that already lives somewhere
+//! else. [`WaterChain`] queues `WaterBuffer`s, shared and static byte slices without copying
+//! them together: [`WaterChain::as_io_slices`] gathers them for `writev`,
+//! [`WaterChain::advance`] consumes a partial write across segment boundaries.
+//!
+//! Reading works the other way round, [`WaterChain::as_spare_io_slices_mut`] hands out the
+//! spare capacity of every `WaterBuffer` segment for `readv` and
+//! [`WaterChain::advance_mut`] commits the bytes read, filling the segments in order.
+
+use std::collections::VecDeque;
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
+use std::sync::Arc;
+
+use super::WaterBuffer;
+
+/// segments gathered by a single `write_vectored_to` / `read_vectored_from` call
+const MAX_IO_SLICES: usize = 64;
+
+/// A single part of a [`WaterChain`]
+#[derive(Debug)]
+pub enum ChainSegment {
+    /// an owned buffer, its readable window is written and its spare capacity read into
+    Buffer(WaterBuffer<u8>),
+    /// a range of reference counted bytes
+    Shared {
+        /// the shared allocation
+        bytes: Arc<[u8]>,
+        /// offset of the first byte not written yet
+        start: usize,
+        /// offset right after the last byte to write
+        end: usize,
+    },
+    /// bytes living for the whole program
+    Static(&'static [u8]),
+}
+
+impl ChainSegment {
+    /// returning the bytes not consumed yet
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            ChainSegment::Buffer(buffer) => &buffer[..],
+            ChainSegment::Shared { bytes, start, end } => &bytes[*start..*end],
+            ChainSegment::Static(bytes) => bytes,
+        }
+    }
+
+    /// number of bytes not consumed yet
+    pub fn len(&self) -> usize {
+        match self {
+            ChainSegment::Buffer(buffer) => buffer.len(),
+            ChainSegment::Shared { start, end, .. } => end - start,
+            ChainSegment::Static(bytes) => bytes.len(),
+        }
+    }
+
+    /// returns `true` when every byte has been consumed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// consumes `n` bytes, `n` must not exceed [`ChainSegment::len`]
+    fn advance(&mut self, n: usize) {
+        match self {
+            ChainSegment::Buffer(buffer) => buffer.advance(n),
+            ChainSegment::Shared { start, .. } => *start += n,
+            ChainSegment::Static(bytes) => *bytes = &bytes[n..],
+        }
+    }
+}
+
+impl From<WaterBuffer<u8>> for ChainSegment {
+    fn from(buffer: WaterBuffer<u8>) -> Self {
+        ChainSegment::Buffer(buffer)
+    }
+}
+
+impl From<Arc<[u8]>> for ChainSegment {
+    fn from(bytes: Arc<[u8]>) -> Self {
+        let end = bytes.len();
+        ChainSegment::Shared { bytes, start: 0, end }
+    }
+}
+
+impl From<&'static [u8]> for ChainSegment {
+    fn from(bytes: &'static [u8]) -> Self {
+        ChainSegment::Static(bytes)
+    }
+}
+
+impl From<&'static str> for ChainSegment {
+    fn from(bytes: &'static str) -> Self {
+        ChainSegment::Static(bytes.as_bytes())
+    }
+}
+
+/// An ordered list of buffers written and read with vectored IO
+#[derive(Debug, Default)]
+pub struct WaterChain {
+    segments: VecDeque<ChainSegment>,
+}
+
+impl WaterChain {
+    /// creating an empty chain
+    pub fn new() -> WaterChain {
+        WaterChain { segments: VecDeque::new() }
+    }
+
+    /// Appends a segment at the end of the chain
+    pub fn push(&mut self, segment: impl Into<ChainSegment>) {
+        self.segments.push_back(segment.into());
+    }
+
+    /// Removes the first segment, whatever is left of it is returned
+    pub fn pop_front(&mut self) -> Option<ChainSegment> {
+        self.segments.pop_front()
+    }
+
+    /// returning the segments in order
+    pub fn segments(&self) -> impl Iterator<Item = &ChainSegment> {
+        self.segments.iter()
+    }
+
+    /// number of segments
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// total number of bytes not consumed yet
+    pub fn len(&self) -> usize {
+        self.segments.iter().map(ChainSegment::len).sum()
+    }
+
+    /// returns `true` when every segment has been consumed
+    pub fn is_empty(&self) -> bool {
+        self.segments.iter().all(ChainSegment::is_empty)
+    }
+
+    /// Fills `dst` with the non-empty segments in order and returns how many were written
+    pub fn as_io_slices<'a>(&'a self, dst: &mut [IoSlice<'a>]) -> usize {
+        let mut count = 0;
+        let segments = self.segments.iter().filter(|segment| !segment.is_empty());
+        for (slot, segment) in dst.iter_mut().zip(segments) {
+            *slot = IoSlice::new(segment.as_slice());
+            count += 1;
+        }
+        count
+    }
+
+    /// Consumes `n` bytes across segment boundaries.
+    /// Fully consumed segments are dropped.
+    ///
+    /// # Panics
+    /// when `n` is larger than [`WaterChain::len`]
+    pub fn advance(&mut self, mut n: usize) {
+        while n > 0 {
+            let Some(segment) = self.segments.front_mut() else {
+                panic!("Insufficient space to advance");
+            };
+            let len = segment.len();
+            if n < len {
+                segment.advance(n);
+                return;
+            }
+            n -= len;
+            self.segments.pop_front();
+        }
+    }
+
+    /// Fills `dst` with the spare capacity of the `Buffer` segments in order
+    /// and returns how many were written.
+    /// `IoSliceMut` needs initialized bytes, so capacity that was never initialized is zeroed
+    /// the first time it is handed out, later calls hand it out as it is.
+    pub fn as_spare_io_slices_mut<'a>(&'a mut self, dst: &mut [IoSliceMut<'a>]) -> usize {
+        let mut count = 0;
+        let spare = self.segments.iter_mut().filter_map(|segment| match segment {
+            ChainSegment::Buffer(buffer) if buffer.mut_len() > 0 => Some(buffer.init_spare_mut(usize::MAX)),
+            _ => None,
+        });
+        for (slot, chunk) in dst.iter_mut().zip(spare) {
+            *slot = IoSliceMut::new(chunk);
+            count += 1;
+        }
+        count
+    }
+
+    /// Commits `n` bytes read into the slices of [`WaterChain::as_spare_io_slices_mut`],
+    /// filling the spare capacity of the `Buffer` segments in order
+    ///
+    /// # Panics
+    /// when `n` is larger than the total spare capacity
+    pub fn advance_mut(&mut self, mut n: usize) {
+        for segment in self.segments.iter_mut() {
+            if n == 0 {
+                return;
+            }
+            if let ChainSegment::Buffer(buffer) = segment {
+                let step = n.min(buffer.mut_len());
+                buffer.advance_mut(step);
+                n -= step;
+            }
+        }
+        assert!(n == 0, "Chain capacity exceeded");
+    }
+
+    /// Writes the chain with a single vectored write and consumes the written bytes
+    pub fn write_vectored_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> io::Result<usize> {
+        let mut slices = [IoSlice::new(&[]); MAX_IO_SLICES];
+        let count = self.as_io_slices(&mut slices);
+        let n = writer.write_vectored(&slices[..count])?;
+        self.advance(n);
+        Ok(n)
+    }
+
+    /// Reads into the spare capacity of the `Buffer` segments with a single vectored read
+    pub fn read_vectored_from<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<usize> {
+        let n = {
+            let mut slices: [IoSliceMut<'_>; MAX_IO_SLICES] = std::array::from_fn(|_| IoSliceMut::new(&mut []));
+            let count = self.as_spare_io_slices_mut(&mut slices);
+            reader.read_vectored(&mut slices[..count])?
+        };
+        self.advance_mut(n);
+        Ok(n)
+    }
+}
+
+impl<S: Into<ChainSegment>> FromIterator<S> for WaterChain {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        WaterChain {
+            segments: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Human readable formatting for `WaterBuffer<u8>`.
+//!
+//! * `{:?}` prints the buffer state and its contents as an escaped byte string.
+//! * `{:#?}` prints the buffer state followed by a full hexdump.
+//! * `{}` prints the contents as an escaped byte string like `b"GET / ..."`.
+//! * `{:x}` prints a hexdump with an offset column, hex bytes and an ASCII gutter.
+//!
+//! A precision (`{:.64}` / `{:.64x}`) limits how many bytes are printed,
+//! the [`Escaped`] and [`HexDump`] adaptors can also be configured directly.
+
+use std::any::Any;
+use std::fmt;
+
+use super::WaterBuffer;
+
+/// bytes printed by `Display` and `Debug` before the output is truncated
+pub const DEFAULT_FORMAT_LIMIT: usize = 256;
+
+const HEXDUMP_ROW: usize = 16;
+
+/// Display adaptor writing bytes as an escaped byte string literal
+#[derive(Clone, Copy)]
+pub struct Escaped<'a> {
+    first: &'a [u8],
+    second: &'a [u8],
+    limit: usize,
+}
+
+/// Display adaptor writing bytes as a hexdump
+#[derive(Clone, Copy)]
+pub struct HexDump<'a> {
+    first: &'a [u8],
+    second: &'a [u8],
+    limit: usize,
+}
+
+impl<'a> Escaped<'a> {
+    /// creating an adaptor over a plain slice
+    pub const fn new(bytes: &'a [u8]) -> Escaped<'a> {
+        Escaped {
+            first: bytes,
+            second: &[],
+            limit: DEFAULT_FORMAT_LIMIT,
+        }
+    }
+
+    /// limiting the output to the first `limit` bytes
+    pub const fn limit(mut self, limit: usize) -> Escaped<'a> {
+        self.limit = limit;
+        self
+    }
+
+    /// printing every byte regardless of the length
+    pub const fn unlimited(self) -> Escaped<'a> {
+        self.limit(usize::MAX)
+    }
+}
+
+impl<'a> HexDump<'a> {
+    /// creating an adaptor over a plain slice
+    pub const fn new(bytes: &'a [u8]) -> HexDump<'a> {
+        HexDump {
+            first: bytes,
+            second: &[],
+            limit: usize::MAX,
+        }
+    }
+
+    /// limiting the output to the first `limit` bytes
+    pub const fn limit(mut self, limit: usize) -> HexDump<'a> {
+        self.limit = limit;
+        self
+    }
+}
+
+impl WaterBuffer<u8> {
+    /// Returns an adaptor printing the readable bytes as a hexdump
+    pub fn hexdump(&self) -> HexDump<'_> {
+        let (first, second) = self.as_slices();
+        HexDump {
+            first,
+            second,
+            limit: usize::MAX,
+        }
+    }
+
+    /// Returns an adaptor printing the readable bytes as an escaped byte string
+    pub fn escaped(&self) -> Escaped<'_> {
+        let (first, second) = self.as_slices();
+        Escaped {
+            first,
+            second,
+            limit: DEFAULT_FORMAT_LIMIT,
+        }
+    }
+}
+
+fn write_escaped(f: &mut fmt::Formatter<'_>, byte: u8) -> fmt::Result {
+    match byte {
+        b'\n' => f.write_str("\\n"),
+        b'\r' => f.write_str("\\r"),
+        b'\t' => f.write_str("\\t"),
+        b'\0' => f.write_str("\\0"),
+        b'\\' => f.write_str("\\\\"),
+        b'"' => f.write_str("\\\""),
+        0x20..=0x7e => fmt::Write::write_char(f, byte as char),
+        _ => write!(f, "\\x{:02x}", byte),
+    }
+}
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.first.len() + self.second.len();
+        f.write_str("b\"")?;
+        for &byte in self.first.iter().chain(self.second).take(self.limit) {
+            write_escaped(f, byte)?;
+        }
+        f.write_str("\"")?;
+        if total > self.limit {
+            write!(f, "... (+{} bytes)", total - self.limit)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.first.len() + self.second.len();
+        let shown = total.min(self.limit);
+        let mut bytes = self.first.iter().chain(self.second).take(shown);
+        let mut row = [0u8; HEXDUMP_ROW];
+        let mut offset = 0;
+        while offset < shown {
+            let len = (shown - offset).min(HEXDUMP_ROW);
+            for (slot, &byte) in row.iter_mut().zip(bytes.by_ref().take(len)) {
+                *slot = byte;
+            }
+            write!(f, "{:08x} ", offset)?;
+            for (i, byte) in row.iter().enumerate() {
+                if i % 8 == 0 {
+                    f.write_str(" ")?;
+                }
+                if i < len {
+                    write!(f, "{:02x} ", byte)?;
+                } else {
+                    f.write_str("   ")?;
+                }
+            }
+            f.write_str(" |")?;
+            for &byte in &row[..len] {
+                let c = if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' };
+                fmt::Write::write_char(f, c)?;
+            }
+            f.write_str("|\n")?;
+            offset += len;
+        }
+        if total > shown {
+            writeln!(f, "... (+{} bytes)", total - shown)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Debug for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for WaterBuffer<u8> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let escaped = self.escaped();
+        match f.precision() {
+            Some(limit) => fmt::Display::fmt(&escaped.limit(limit), f),
+            None => fmt::Display::fmt(&escaped, f),
+        }
+    }
+}
+
+impl fmt::LowerHex for WaterBuffer<u8> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dump = self.hexdump();
+        match f.precision() {
+            Some(limit) => fmt::Display::fmt(&dump.limit(limit), f),
+            None => fmt::Display::fmt(&dump, f),
+        }
+    }
+}
+
+/// Byte buffers print their contents escaped (or as a hexdump with `{:#?}`),
+/// other element types print the readable elements as a slice
+impl<T: fmt::Debug + 'static> fmt::Debug for WaterBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(bytes) = (self as &dyn Any).downcast_ref::<WaterBuffer<u8>>() {
+            return bytes.fmt_bytes(f);
+        }
+        let data = &self[..];
+        f.debug_struct("WaterBuffer")
+            .field("len", &data.len())
+            .field("cap", &self.cap)
+            .field("start_pos", &self.start_pos)
+            .field("data", &data)
+            .finish()
+    }
+}
+
+impl WaterBuffer<u8> {
+    fn fmt_bytes(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            writeln!(
+                f,
+                "WaterBuffer {{ len: {}, cap: {}, start_pos: {} }}",
+                self.len(),
+                self.cap,
+                self.start_pos
+            )?;
+            return fmt::Display::fmt(&self.hexdump().limit(f.precision().unwrap_or(usize::MAX)), f);
+        }
+        let data = self.escaped().limit(f.precision().unwrap_or(DEFAULT_FORMAT_LIMIT));
+        f.debug_struct("WaterBuffer")
+            .field("len", &self.len())
+            .field("cap", &self.cap)
+            .field("start_pos", &self.start_pos)
+            .field("data", &data)
+            .finish()
+    }
+}
@@ -0,0 +1,218 @@
+//! Standard trait implementations for `WaterBuffer<u8>`.
+//!
+//! Every comparison here works on the logical contents returned by
+//! [`WaterBuffer::as_slices`], so a wrapped circular buffer compares, hashes and
+//! converts the same way as a linear one holding the same bytes.
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::iter::Chain;
+use std::ptr;
+use std::slice::Iter;
+
+use super::WaterBuffer;
+
+impl WaterBuffer<u8> {
+    /// iterating the readable bytes in logical order
+    #[inline]
+    fn logical_iter(&self) -> Chain<Iter<'_, u8>, Iter<'_, u8>> {
+        let (first, second) = self.as_slices();
+        first.iter().chain(second.iter())
+    }
+
+    /// comparing the readable bytes against a contiguous slice
+    #[inline]
+    fn eq_slice(&self, other: &[u8]) -> bool {
+        let (first, second) = self.as_slices();
+        first.len() + second.len() == other.len()
+            && &other[..first.len()] == first
+            && &other[first.len()..] == second
+    }
+}
+
+impl Clone for WaterBuffer<u8> {
+    /// deep copy of the readable bytes into a new allocation with the same capacity
+    fn clone(&self) -> Self {
+        let mut buffer = WaterBuffer::with_capacity(self.cap);
+        let (first, second) = self.as_slices();
+        unsafe {
+            ptr::copy_nonoverlapping(first.as_ptr(), buffer.pointer, first.len());
+            ptr::copy_nonoverlapping(second.as_ptr(), buffer.pointer.add(first.len()), second.len());
+        }
+        buffer.filled_data_length = first.len() + second.len();
+        buffer
+    }
+}
+
+impl Default for WaterBuffer<u8> {
+    fn default() -> Self {
+        WaterBuffer::with_capacity(0)
+    }
+}
+
+impl PartialEq for WaterBuffer<u8> {
+    fn eq(&self, other: &Self) -> bool {
+        match other.as_slices() {
+            (first, []) => self.eq_slice(first),
+            _ => self.len() == other.len() && self.logical_iter().eq(other.logical_iter()),
+        }
+    }
+}
+
+impl Eq for WaterBuffer<u8> {}
+
+macro_rules! impl_partial_eq {
+    ($($ty:ty => |$other:ident| $as_slice:expr),* $(,)?) => {
+        $(
+            impl PartialEq<$ty> for WaterBuffer<u8> {
+                fn eq(&self, $other: &$ty) -> bool {
+                    self.eq_slice($as_slice)
+                }
+            }
+
+            impl PartialEq<WaterBuffer<u8>> for $ty {
+                fn eq(&self, other: &WaterBuffer<u8>) -> bool {
+                    let $other = self;
+                    other.eq_slice($as_slice)
+                }
+            }
+        )*
+    };
+}
+
+impl_partial_eq! {
+    [u8] => |other| other,
+    &[u8] => |other| other,
+    Vec<u8> => |other| other.as_slice(),
+    str => |other| other.as_bytes(),
+    &str => |other| other.as_bytes(),
+}
+
+impl<const N: usize> PartialEq<[u8; N]> for WaterBuffer<u8> {
+    fn eq(&self, other: &[u8; N]) -> bool {
+        self.eq_slice(other)
+    }
+}
+
+impl<const N: usize> PartialEq<&[u8; N]> for WaterBuffer<u8> {
+    fn eq(&self, other: &&[u8; N]) -> bool {
+        self.eq_slice(*other)
+    }
+}
+
+/// block size used when feeding the readable bytes to a hasher
+const HASH_BLOCK: usize = 256;
+
+impl Hash for WaterBuffer<u8> {
+    /// hashes the length followed by the logical contents in fixed size blocks,
+    /// so buffers that are equal under `Eq` hash equally no matter where a circular buffer wraps.
+    /// The result is consistent with `Eq` only, it is not the hash of the equivalent `[u8]`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let (mut first, mut second) = self.as_slices();
+        state.write_usize(first.len() + second.len());
+        while first.len() >= HASH_BLOCK {
+            state.write(&first[..HASH_BLOCK]);
+            first = &first[HASH_BLOCK..];
+        }
+        if !first.is_empty() && !second.is_empty() {
+            // the block straddling the wrap point is stitched together on the stack
+            let mut block = [0u8; HASH_BLOCK];
+            let take = (HASH_BLOCK - first.len()).min(second.len());
+            block[..first.len()].copy_from_slice(first);
+            block[first.len()..first.len() + take].copy_from_slice(&second[..take]);
+            state.write(&block[..first.len() + take]);
+            first = &[];
+            second = &second[take..];
+        }
+        for block in first.chunks(HASH_BLOCK).chain(second.chunks(HASH_BLOCK)) {
+            state.write(block);
+        }
+    }
+}
+
+impl PartialOrd for WaterBuffer<u8> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for WaterBuffer<u8> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.as_slices(), other.as_slices()) {
+            ((first, []), (other_first, [])) => first.cmp(other_first),
+            _ => self.logical_iter().cmp(other.logical_iter()),
+        }
+    }
+}
+
+impl From<Vec<u8>> for WaterBuffer<u8> {
+    /// adopts the vector allocation without copying
+    fn from(vec: Vec<u8>) -> Self {
+        WaterBuffer::from_vec(vec)
+    }
+}
+
+impl From<Box<[u8]>> for WaterBuffer<u8> {
+    fn from(boxed: Box<[u8]>) -> Self {
+        WaterBuffer::from_vec(boxed.into_vec())
+    }
+}
+
+impl From<&[u8]> for WaterBuffer<u8> {
+    fn from(slice: &[u8]) -> Self {
+        let mut buffer = WaterBuffer::with_capacity(slice.len());
+        buffer.extend_from_slice(slice);
+        buffer
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for WaterBuffer<u8> {
+    fn from(slice: &[u8; N]) -> Self {
+        WaterBuffer::from(&slice[..])
+    }
+}
+
+impl From<&str> for WaterBuffer<u8> {
+    fn from(s: &str) -> Self {
+        WaterBuffer::from(s.as_bytes())
+    }
+}
+
+impl From<WaterBuffer<u8>> for Vec<u8> {
+    /// hands the allocation over to the vector, compacting the readable bytes to the front first
+    fn from(buffer: WaterBuffer<u8>) -> Vec<u8> {
+        buffer.into_vec()
+    }
+}
+
+impl From<WaterBuffer<u8>> for Box<[u8]> {
+    fn from(buffer: WaterBuffer<u8>) -> Box<[u8]> {
+        buffer.into_boxed_slice()
+    }
+}
+
+impl FromIterator<u8> for WaterBuffer<u8> {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut buffer = WaterBuffer::with_capacity(iter.size_hint().0);
+        buffer.extend(iter);
+        buffer
+    }
+}
+
+impl Extend<u8> for WaterBuffer<u8> {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        #[cfg(not(feature = "circular_buffer"))]
+        self.reserve(iter.size_hint().0);
+        for byte in iter {
+            self.push(byte);
+        }
+    }
+}
+
+impl<'a> Extend<&'a u8> for WaterBuffer<u8> {
+    fn extend<I: IntoIterator<Item = &'a u8>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
@@ -0,0 +1,295 @@
+//! mmap-backed buffers, enabled with the `mmap` feature on unix targets.
+//!
+//! * [`WaterMmap`] maps a file read-only, so multi-GB captures can be parsed without
+//!   reading them into memory first. Create it with [`WaterBuffer::from_file_mmap`].
+//!   It exposes the same readable window API as `WaterBuffer`: `Deref<Target = [u8]>`,
+//!   `len`, `advance`.
+//! * [`WaterBuffer::with_anonymous_mmap`] puts a `WaterBuffer` on anonymous memory. On Linux
+//!   it grows with `mremap`, which moves page table entries instead of copying pages, other
+//!   unix targets fall back to mapping a larger region and copying.
+//! * [`WaterBuffer::with_huge_pages`] backs a `WaterBuffer` with 2 MiB pages, trying
+//!   `MAP_HUGETLB` first and falling back to `madvise(MADV_HUGEPAGE)` on regular memory
+//!   when no huge pages are reserved.
+//!
+//! Mapped buffers keep their [`PageMode`] while growing and shrinking,
+//! capacities are rounded up to whole pages.
+
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+
+use super::WaterBuffer;
+
+/// returning the size of a memory page
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+/// size of a huge page on x86_64 and aarch64 with 4 KiB base pages
+pub const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// Maps `len` bytes of fresh anonymous memory, `flags` are added to the mapping flags
+fn map_anonymous(len: usize, flags: libc::c_int) -> io::Result<*mut u8> {
+    let pointer = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    if pointer == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(pointer as *mut u8)
+}
+
+impl WaterBuffer<u8> {
+    /// Maps the file at `path` read-only.
+    ///
+    /// # Safety
+    /// The file must not be truncated or modified while it is mapped,
+    /// shrinking it makes accessing the missing pages raise `SIGBUS`.
+    pub unsafe fn from_file_mmap(path: impl AsRef<Path>) -> io::Result<WaterMmap> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let len = usize::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file too large to map"))?;
+        if len == 0 {
+            return Ok(WaterMmap { pointer: ptr::NonNull::dangling().as_ptr(), mapped: 0, start_pos: 0 });
+        }
+        let pointer = unsafe { libc::mmap(ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if pointer == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(WaterMmap { pointer: pointer as *mut u8, mapped: len, start_pos: 0 })
+    }
+}
+
+/// A read-only file mapping with a `WaterBuffer` style readable window
+#[derive(Debug)]
+pub struct WaterMmap {
+    pointer: *mut u8,
+    /// mapped length, the size of the file when it was mapped
+    mapped: usize,
+    start_pos: usize,
+}
+
+// the mapping is never written to and owned by this value
+unsafe impl Send for WaterMmap {}
+unsafe impl Sync for WaterMmap {}
+
+impl WaterMmap {
+    /// number of readable bytes
+    #[inline(always)]
+    pub const fn len(&self) -> usize {
+        self.mapped - self.start_pos
+    }
+
+    /// returns `true` when there is nothing to read
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// size of the mapped file
+    #[inline(always)]
+    pub const fn mapped_len(&self) -> usize {
+        self.mapped
+    }
+
+    /// Consumes `n` readable bytes
+    ///
+    /// # Panics
+    /// when `n` is larger than [`WaterMmap::len`]
+    pub const fn advance(&mut self, n: usize) {
+        if n > self.len() {
+            panic!("Insufficient space to advance");
+        }
+        self.start_pos += n;
+    }
+
+    /// Tells the kernel the file is read front to back, so it reads ahead more aggressively
+    pub fn advise_sequential(&self) -> io::Result<()> {
+        if self.mapped == 0 {
+            return Ok(());
+        }
+        match unsafe { libc::madvise(self.pointer as *mut libc::c_void, self.mapped, libc::MADV_SEQUENTIAL) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+}
+
+impl Deref for WaterMmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::slice::from_raw_parts(self.pointer.add(self.start_pos), self.len()) }
+    }
+}
+
+impl Drop for WaterMmap {
+    fn drop(&mut self) {
+        if self.mapped > 0 {
+            unsafe { libc::munmap(self.pointer as *mut libc::c_void, self.mapped) };
+        }
+    }
+}
+
+/// Kind of pages backing a `WaterBuffer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PageMode {
+    /// regular pages, a `WaterBuffer` in this mode uses the global allocator
+    Regular,
+    /// regular pages on an anonymous mapping, grown with `mremap` on Linux
+    Mapped,
+    /// transparent huge pages requested with `madvise(MADV_HUGEPAGE)`,
+    /// the kernel uses them when it can and regular pages otherwise
+    Transparent,
+    /// huge pages reserved in hugetlbfs, mapped with `MAP_HUGETLB`
+    HugeTlb,
+}
+
+impl PageMode {
+    /// allocation granularity of the mode
+    fn granularity(self) -> usize {
+        match self {
+            PageMode::Regular | PageMode::Mapped => page_size(),
+            PageMode::Transparent | PageMode::HugeTlb => HUGE_PAGE_SIZE,
+        }
+    }
+
+    /// rounds `len` up to the granularity of the mode
+    fn round(self, len: usize) -> usize {
+        let granularity = self.granularity();
+        len.div_ceil(granularity) * granularity
+    }
+}
+
+/// Maps `len` bytes for `mode`, dropping `mode` from `HugeTlb` to `Transparent`
+/// when no huge pages are reserved
+fn map_pages(len: usize, mode: &mut PageMode) -> io::Result<*mut u8> {
+    #[cfg(target_os = "linux")]
+    if *mode == PageMode::HugeTlb {
+        if let Ok(pointer) = map_anonymous(len, libc::MAP_HUGETLB) {
+            return Ok(pointer);
+        }
+        *mode = PageMode::Transparent;
+    }
+    let pointer = map_anonymous(len, 0)?;
+    #[cfg(target_os = "linux")]
+    if *mode == PageMode::Transparent {
+        // only a hint, kernels without THP support keep using regular pages
+        unsafe { libc::madvise(pointer as *mut libc::c_void, len, libc::MADV_HUGEPAGE) };
+    }
+    Ok(pointer)
+}
+
+/// Moves a mapping made by [`map_pages`] from `cap` to `new_cap` bytes, keeping the first `keep` bytes.
+///
+/// # Safety
+/// `pointer` and `cap` must describe a live mapping made for `mode`, `keep` must not exceed
+/// either size. The old mapping is gone when this succeeds.
+unsafe fn remap_pages(pointer: *mut u8, cap: usize, new_cap: usize, keep: usize, mode: &mut PageMode) -> io::Result<*mut u8> {
+    // hugetlb mappings can not always be resized in place, they are copied like outside Linux
+    #[cfg(target_os = "linux")]
+    if *mode != PageMode::HugeTlb {
+        let pointer = unsafe { libc::mremap(pointer as *mut libc::c_void, cap, new_cap, libc::MREMAP_MAYMOVE) };
+        if pointer == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        return Ok(pointer as *mut u8);
+    }
+    let new_pointer = map_pages(new_cap, mode)?;
+    unsafe {
+        ptr::copy_nonoverlapping(pointer, new_pointer, keep);
+        unmap_pages(pointer, cap);
+    }
+    Ok(new_pointer)
+}
+
+/// Unmaps a mapping made by [`map_pages`]
+///
+/// # Safety
+/// `pointer` and `cap` must describe a live mapping that is not used afterwards
+pub(crate) unsafe fn unmap_pages(pointer: *mut u8, cap: usize) {
+    unsafe { libc::munmap(pointer as *mut libc::c_void, cap) };
+}
+
+impl WaterBuffer<u8> {
+    /// Creating a buffer of at least `cap` bytes on anonymous memory, rounded up to whole pages.
+    /// Growing moves the mapping with `mremap` on Linux instead of copying the data,
+    /// the global allocator is used when nothing can be mapped at all,
+    /// check [`WaterBuffer::page_mode`] for the outcome.
+    pub fn with_anonymous_mmap(cap: usize) -> WaterBuffer<u8> {
+        WaterBuffer::with_pages(cap, PageMode::Mapped)
+    }
+
+    /// Creating a buffer of at least `cap` bytes rounded up to [`HUGE_PAGE_SIZE`].
+    /// Reserved huge pages are used when available, otherwise anonymous memory advised for
+    /// transparent huge pages, and the global allocator when nothing can be mapped at all.
+    /// Growing, shrinking and dropping the buffer stay on the chosen pages,
+    /// check [`WaterBuffer::page_mode`] for the outcome.
+    /// Outside Linux this is the same as [`WaterBuffer::with_capacity`].
+    pub fn with_huge_pages(cap: usize) -> WaterBuffer<u8> {
+        if !cfg!(target_os = "linux") {
+            return WaterBuffer::with_capacity(cap);
+        }
+        WaterBuffer::with_pages(cap, PageMode::HugeTlb)
+    }
+
+    /// maps at least one page for `mode`, falling back to the global allocator
+    fn with_pages(cap: usize, mut mode: PageMode) -> WaterBuffer<u8> {
+        let cap = mode.round(cap.max(1));
+        match map_pages(cap, &mut mode) {
+            Ok(pointer) => {
+                let mut buffer = WaterBuffer::from_allocation(pointer, cap, 0, 0);
+                // fresh anonymous pages are zero-filled
+                buffer.initialized = cap;
+                buffer.page_mode = mode;
+                buffer
+            }
+            Err(_) => WaterBuffer::with_capacity(cap),
+        }
+    }
+
+    /// returning the kind of pages backing the buffer,
+    /// [`PageMode::Regular`] for buffers on the global allocator
+    pub const fn page_mode(&self) -> PageMode {
+        self.page_mode
+    }
+
+    /// Moves a buffer on mapped pages to at least `new_cap` bytes, rounded up to the page granularity.
+    /// Callers make sure `start_pos + filled_data_length <= new_cap`.
+    pub(crate) fn resize_mapping(&mut self, new_cap: usize) {
+        let new_cap = self.page_mode.round(new_cap);
+        if new_cap == self.cap {
+            return;
+        }
+        if new_cap == 0 {
+            unsafe { unmap_pages(self.pointer, self.cap) };
+            self.pointer = ptr::NonNull::dangling().as_ptr();
+            self.cap = 0;
+            self.initialized = 0;
+            return;
+        }
+        let keep = self.start_pos + self.filled_data_length;
+        let result = if self.cap == 0 {
+            map_pages(new_cap, &mut self.page_mode)
+        } else {
+            unsafe { remap_pages(self.pointer, self.cap, new_cap, keep, &mut self.page_mode) }
+        };
+        let Ok(pointer) = result else {
+            panic!("Failed to reallocate WaterBuffer: Out of memory");
+        };
+        // fresh pages are zero-filled, moved ones keep what was known before
+        self.initialized = if self.cap == 0 { new_cap } else { self.initialized.min(keep) };
+        self.pointer = pointer;
+        self.cap = new_cap;
+    }
+}
@@ -5,20 +5,55 @@
 //! It provides safe memory management, dynamic resizing, iteration, and indexing.
 
 use std::alloc::{alloc, dealloc, realloc, Layout};
-use std::mem::MaybeUninit;
+use std::mem::{ManuallyDrop, MaybeUninit};
 use std::ops::{Deref, DerefMut, Index, IndexMut, Range, RangeFrom, RangeFull, RangeTo};
 use std::ptr;
 #[cfg(feature = "bytes")]
 use bytes::buf::UninitSlice;
 #[cfg(feature = "bytes")]
 use bytes::BufMut;
+
+#[cfg(not(feature = "circular_buffer"))]
+mod chain;
+mod format;
+mod impls;
+#[cfg(all(unix, feature = "mmap"))]
+mod mmap;
+#[cfg(not(feature = "circular_buffer"))]
+mod prefix;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(not(feature = "circular_buffer"))]
+mod rope;
+#[cfg(not(feature = "circular_buffer"))]
+pub(crate) mod search;
+#[cfg(not(feature = "circular_buffer"))]
+mod shrink;
+#[cfg(not(feature = "circular_buffer"))]
+mod std_io;
+#[cfg(feature = "uring")]
+mod uring;
+mod varint;
+#[cfg(not(feature = "circular_buffer"))]
+pub use shrink::*;
+#[cfg(not(feature = "circular_buffer"))]
+pub use chain::{ChainSegment, WaterChain};
+#[cfg(not(feature = "circular_buffer"))]
+pub use rope::{WaterRope, DEFAULT_SEGMENT_SIZE};
+#[cfg(not(feature = "circular_buffer"))]
+pub use search::Matches;
+pub use format::{Escaped, HexDump, DEFAULT_FORMAT_LIMIT};
+pub use varint::*;
+#[cfg(all(unix, feature = "mmap"))]
+pub use mmap::{PageMode, WaterMmap, HUGE_PAGE_SIZE};
 #[cfg(feature = "uring")]
-use tokio_uring::buf::{BoundedBuf, BoundedBufMut, IoBuf, IoBufMut};
+pub use uring::{WaterReadSlice, WaterWriteSlice};
+#[cfg(not(feature = "circular_buffer"))]
+pub use prefix::*;
 
 
 type InnerType = u8;
 
-#[derive(Debug)]
 /// Main dynamic buffer struct
 pub struct WaterBuffer<T> {
     pub (crate) cap: usize,
@@ -30,6 +65,12 @@ pub struct WaterBuffer<T> {
     pub (crate) original:Option<*mut WaterBuffer<T>>,
     pub (crate) filled_data_length
     : usize,
+    /// bytes from the start of the allocation known to be initialized,
+    /// so handing out zeroed spare capacity zeroes every byte at most once
+    pub(crate) initialized: usize,
+    /// pages backing the allocation, everything but `Regular` is an anonymous mapping
+    #[cfg(all(unix, feature = "mmap"))]
+    pub(crate) page_mode: PageMode,
 }
 
 
@@ -47,7 +88,10 @@ impl  WaterBuffer<InnerType> {
             pointer:self.pointer,
             start_pos:self.start_pos,
             filled_data_length:self.filled_data_length,
-            original:Some(original)
+            initialized:self.initialized,
+            original:Some(original),
+            #[cfg(all(unix, feature = "mmap"))]
+            page_mode:self.page_mode,
         }
     }
 }
@@ -72,6 +116,8 @@ impl WaterBuffer<InnerType> {
 
 
     pub fn spare_capacity_mut(&mut self)->&mut [MaybeUninit<u8>]{
+        // the caller may de-initialize anything it is handed
+        self.initialized = self.initialized.min(self.start_pos + self.filled_data_length);
         unsafe {
             let pos = self.start_pos + self.filled_data_length;
             let pointer = self.pointer.add(pos);
@@ -79,6 +125,59 @@ impl WaterBuffer<InnerType> {
         }
     }
 
+    /// Returns up to `limit` bytes of the spare capacity initialized,
+    /// for readers that only accept `&mut [u8]`. Commit written bytes with `advance_mut`.
+    /// Only bytes that were never initialized before are zeroed.
+    #[cfg(any(feature = "futures-io", not(feature = "circular_buffer")))]
+    pub(crate) fn init_spare_mut(&mut self, limit: usize) -> &mut [u8] {
+        let pos = self.start_pos + self.filled_data_length;
+        let end = pos + (self.cap - pos).min(limit);
+        unsafe {
+            if self.initialized < end {
+                let from = self.initialized.max(pos);
+                ptr::write_bytes(self.pointer.add(from), 0, end - from);
+                // the readable window in between is initialized, consumed bytes
+                // in front of it only when the mark already reaches them
+                if self.initialized >= self.start_pos {
+                    self.initialized = end;
+                }
+            }
+            // SAFETY: `pos..end` is either below the mark or has just been zeroed
+            std::slice::from_raw_parts_mut(self.pointer.add(pos), end - pos)
+        }
+    }
+
+    /// Returns the readable data as two slices in logical order.
+    /// The second slice is only non-empty when a circular buffer has wrapped around.
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        #[cfg(feature = "circular_buffer")]
+        if let Some(p) = self.circular_position
+            && p > 0 && p < self.cap && self.filled_data_length >= self.cap {
+            return unsafe {
+                (
+                    std::slice::from_raw_parts(self.pointer.add(p), self.cap - p),
+                    std::slice::from_raw_parts(self.pointer, p),
+                )
+            };
+        }
+        (&self[..], &[])
+    }
+
+    /// Moves the readable data to the beginning of the allocation in logical order
+    pub(crate) fn make_linear(&mut self) {
+        #[cfg(feature = "circular_buffer")]
+        if let Some(p) = self.circular_position.take()
+            && self.filled_data_length >= self.cap {
+            unsafe { std::slice::from_raw_parts_mut(self.pointer, self.cap) }.rotate_left(p);
+            self.start_pos = 0;
+            self.filled_data_length = self.cap;
+            return;
+        }
+        if self.start_pos > 0 {
+            self.shift_data();
+        }
+    }
+
     /// Returns a mutable iterator over the buffer
     pub fn iter_mut(&mut self) -> WaterBufferIterMut<'_> {
         WaterBufferIterMut {
@@ -102,55 +201,123 @@ impl WaterBuffer<InnerType> {
 
     /// Creates a new buffer with a given capacity
     pub fn with_capacity(cap: usize) -> WaterBuffer<InnerType> {
-        let layout = Layout::array::<InnerType>(cap).unwrap();
-        let first_element_pointer = unsafe { alloc(layout) } as *mut InnerType;
-        #[cfg(feature = "circular_buffer")]
-        {
-            return  WaterBuffer {
-                cap,
-                pointer: first_element_pointer,
-                start_pos: 0,
-                filled_data_length
-                : 0,
-                circular_position:None
-            };
+        let first_element_pointer = if cap == 0 {
+            ptr::NonNull::<InnerType>::dangling().as_ptr()
+        } else {
+            let layout = Layout::array::<InnerType>(cap).unwrap();
+            unsafe { alloc(layout) as *mut InnerType }
         };
+        Self::from_allocation(first_element_pointer, cap, 0, 0)
+    }
 
-        #[cfg(all(not(feature = "circular_buffer")))]
-        {
+    /// Builds a buffer around an allocation made with `Layout::array::<u8>(cap)`
+    #[inline(always)]
+    pub(crate) const fn from_allocation(
+        pointer: *mut InnerType,
+        cap: usize,
+        start_pos: usize,
+        filled_data_length: usize,
+    ) -> WaterBuffer<InnerType> {
+        WaterBuffer {
+            cap,
+            start_pos,
+            #[cfg(feature = "circular_buffer")]
+            circular_position: None,
+            pointer,
             #[cfg(feature = "unsafe_clone")]
-            {
-                return   WaterBuffer {
-                    cap,
-                    pointer: first_element_pointer,
-                    start_pos: 0,
-                    filled_data_length
-                    : 0,
-                    original:None
-                }
-            }
-            #[cfg(not(feature = "unsafe_clone"))]
-            {
-                return   WaterBuffer {
-                    cap,
-                    pointer: first_element_pointer,
-                    start_pos: 0,
-                    filled_data_length
-                    : 0,
-                }
-            }
+            original: None,
+            filled_data_length,
+            initialized: if start_pos == 0 { filled_data_length } else { 0 },
+            #[cfg(all(unix, feature = "mmap"))]
+            page_mode: PageMode::Regular,
+        }
+    }
+
+    /// Adopts the allocation of `vec` without copying.
+    /// The vector length becomes the readable length and its capacity the buffer capacity.
+    pub fn from_vec(vec: Vec<u8>) -> WaterBuffer<InnerType> {
+        let mut vec = ManuallyDrop::new(vec);
+        Self::from_allocation(vec.as_mut_ptr(), vec.capacity(), 0, vec.len())
+    }
+
+    /// Hands the allocation over to a `Vec<u8>` without copying.
+    /// The readable bytes are moved to the front first, so consumed bytes before `start_pos` are dropped.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        #[cfg(feature = "unsafe_clone")]
+        if self.original.is_some() {
+            // the allocation belongs to the original buffer
+            return self[..].to_vec();
+        }
+        self.make_linear();
+        let (pointer, len, cap) = self.into_raw_parts();
+        if cap == 0 {
+            return Vec::new();
+        }
+        unsafe { Vec::from_raw_parts(pointer, len, cap) }
+    }
+
+    /// Converts the buffer into a boxed slice of the readable bytes.
+    /// This only reallocates when there is spare capacity left.
+    pub fn into_boxed_slice(self) -> Box<[u8]> {
+        self.into_vec().into_boxed_slice()
+    }
+
+    /// Creates a buffer directly from a pointer, a readable length and a capacity.
+    ///
+    /// # Safety
+    /// `pointer` must have been allocated by the global allocator with `Layout::array::<u8>(cap)`
+    /// (as `Vec<u8>` and `WaterBuffer` do), or be dangling when `cap` is zero.
+    /// The first `len` bytes must be initialized, `len` must not exceed `cap`,
+    /// and the allocation is owned by the returned buffer from now on.
+    pub unsafe fn from_raw_parts(pointer: *mut u8, len: usize, cap: usize) -> WaterBuffer<InnerType> {
+        Self::from_allocation(pointer, cap, 0, len)
+    }
+
+    /// Decomposes the buffer into `(pointer, len, cap)` after compacting the readable bytes to the front.
+    /// The caller becomes responsible for the allocation, e.g. by passing the parts back to
+    /// [`WaterBuffer::from_raw_parts`] or `Vec::from_raw_parts`.
+    pub fn into_raw_parts(mut self) -> (*mut u8, usize, usize) {
+        #[cfg(all(unix, feature = "mmap"))]
+        if self.page_mode != PageMode::Regular {
+            // mapped pages can not be handed to the global allocator, the readable bytes move to the heap
+            return WaterBuffer::from(&self[..]).into_raw_parts();
         }
+        self.make_linear();
+        let buffer = ManuallyDrop::new(self);
+        (buffer.pointer, buffer.len(), buffer.cap)
     }
 
     /// Expands the buffer to a new capacity
     #[inline(always)]
-    pub fn expand(&mut self, mut additional: usize) {
-        let new_cap = self.cap + additional;
-        let old_layout = Layout::array::<InnerType>(self.cap).unwrap();
-        let new_size = new_cap * std::mem::size_of::<InnerType>();
+    pub fn expand(&mut self, additional: usize) {
+        self.resize_allocation(self.cap + additional);
+    }
 
+    /// Moves the allocation to exactly `new_cap` bytes, keeping the readable data intact,
+    /// buffers on mapped pages round `new_cap` up to their page granularity.
+    /// Callers must make sure `start_pos + filled_data_length <= new_cap`.
+    fn resize_allocation(&mut self, new_cap: usize) {
+        #[cfg(all(unix, feature = "mmap"))]
+        if self.page_mode != PageMode::Regular {
+            return self.resize_mapping(new_cap);
+        }
+        if new_cap == self.cap {
+            return;
+        }
         unsafe {
-            let new_ptr = realloc(self.pointer as *mut u8, old_layout, new_size);
+            if new_cap == 0 {
+                dealloc(self.pointer, Layout::array::<InnerType>(self.cap).unwrap());
+                self.pointer = ptr::NonNull::<InnerType>::dangling().as_ptr();
+                self.cap = 0;
+                self.initialized = 0;
+                return;
+            }
+            let new_ptr = if self.cap == 0 {
+                alloc(Layout::array::<InnerType>(new_cap).unwrap())
+            } else {
+                let old_layout = Layout::array::<InnerType>(self.cap).unwrap();
+                realloc(self.pointer, old_layout, new_cap * std::mem::size_of::<InnerType>())
+            };
 
             if new_ptr.is_null() {
                 // Handle OOM (Out of Memory) gracefully
@@ -159,10 +326,31 @@ impl WaterBuffer<InnerType> {
 
             self.pointer = new_ptr as *mut InnerType;
             self.cap = new_cap;
+            self.initialized = self.initialized.min(new_cap);
         }
     }
 
     #[cfg(not(feature = "circular_buffer"))]
+    /// Shrinks the capacity as much as possible while keeping the readable data.
+    /// Consumed bytes before `start_pos` are compacted away first.
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    #[cfg(not(feature = "circular_buffer"))]
+    /// Shrinks the capacity down to `min_capacity`, or to the readable length if it is larger.
+    /// Has no effect when the current capacity is already below that bound.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let new_cap = self.filled_data_length.max(min_capacity);
+        if new_cap >= self.cap {
+            return;
+        }
+        if self.start_pos > 0 {
+            self.shift_data();
+        }
+        self.resize_allocation(new_cap);
+    }
+
     /// Calculates an appropriate size for buffer growth
     #[inline(always)]
     pub(crate) const fn ap_size(&self, additional: usize) -> usize {
@@ -342,11 +530,11 @@ impl WaterBuffer<InnerType> {
 
 
 
+    /// Moves the readable data to the front of the allocation
     #[inline(always)]
-
-     const fn shift_data(&mut self){
+    const fn shift_data(&mut self){
         unsafe{
-            ptr::copy_nonoverlapping(
+            ptr::copy(
                 self.pointer.add(self.start_pos),
                 self.pointer,
                 self.filled_data_length
@@ -387,7 +575,7 @@ impl WaterBuffer<InnerType> {
         unsafe {
             ptr::copy_nonoverlapping(
                 slice.as_ptr(),
-                self.pointer.add(self.start_pos + self.filled_data_length) as *mut u8,
+                self.pointer.add(self.start_pos + self.filled_data_length),
                 len,
             )
         };
@@ -408,6 +596,12 @@ impl WaterBuffer<InnerType> {
 
     }
 
+    /// Returns `true` when there is nothing left to read
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
 
     #[cfg(feature = "circular_buffer")]
     #[inline(always)]
@@ -506,8 +700,9 @@ impl WaterBuffer<InnerType> {
     #[inline(always)]
     pub const fn un_initialized_remaining(&self) -> usize {
         let mut pos = self.filled_data_length;
-        if let Some(p) = self.circular_position {
-            if p > 0 {pos = p;}
+        if let Some(p) = self.circular_position
+            && p > 0 {
+            pos = p;
         }
         if pos > self.cap { return  0;}
         self.cap - pos
@@ -527,7 +722,6 @@ impl WaterBuffer<InnerType> {
 
 
     #[cfg(not(feature = "circular_buffer"))]
-
     #[inline(always)]
     pub const fn chunk_mut(&mut self) -> &mut [u8] {
         unsafe {
@@ -537,7 +731,6 @@ impl WaterBuffer<InnerType> {
         }
     }
     #[cfg(not(feature = "circular_buffer"))]
-
     #[inline(always)]
     pub const fn chunk(&self) -> &[u8] {
         unsafe {
@@ -600,10 +793,10 @@ unsafe impl  BufMut for WaterBuffer<InnerType>{
         }
     }
 }
-impl Into<WaterBufferOwnedIter<InnerType>> for WaterBuffer<InnerType> {
-    fn into(self) -> WaterBufferOwnedIter<InnerType> {
+impl From<WaterBuffer<InnerType>> for WaterBufferOwnedIter<InnerType> {
+    fn from(buffer: WaterBuffer<InnerType>) -> WaterBufferOwnedIter<InnerType> {
         WaterBufferOwnedIter {
-            buffer: self,
+            buffer,
             iterator_pos: 0,
         }
     }
@@ -622,11 +815,17 @@ impl<T> Drop for WaterBuffer<T> {
                    e.pointer = self.pointer;
                    e.start_pos = self.start_pos;
                    e.filled_data_length = self.filled_data_length;
+                   e.initialized = self.initialized;
                    return
                }
            }
        }
         if !self.pointer.is_null() && self.cap > 0 {
+            #[cfg(all(unix, feature = "mmap"))]
+            if self.page_mode != PageMode::Regular {
+                unsafe { mmap::unmap_pages(self.pointer as *mut u8, self.cap * std::mem::size_of::<T>()) };
+                return;
+            }
             let layout = Layout::array::<T>(self.cap).unwrap();
             unsafe {
                 dealloc(self.pointer as *mut u8, layout);
@@ -723,7 +922,7 @@ impl<T> Index<RangeFrom<usize>> for WaterBuffer<T> {
         }
         unsafe { std::slice::from_raw_parts(
             self.pointer.add(self.start_pos + idx.start ),
-            self.filled_data_length)
+            self.filled_data_length - idx.start)
         }
     }
 }
@@ -735,7 +934,7 @@ impl<T> IndexMut<RangeFrom<usize>> for WaterBuffer<T> {
         }
         unsafe { std::slice::from_raw_parts_mut(
             self.pointer.add(self.start_pos + idx.start ),
-            self.filled_data_length)
+            self.filled_data_length - idx.start)
         }
     }
 }impl<T> IndexMut<RangeTo<usize>> for WaterBuffer<T> {
@@ -775,18 +974,18 @@ impl<T> Index<Range<usize>> for WaterBuffer<T> {
             panic!("Range out of bounds");
         }
         unsafe { std::slice::from_raw_parts(self.pointer.add(self.start_pos + idx.start),
-                                            self.start_pos + idx.end) }
+                                            idx.end - idx.start) }
     }
 }
 
 impl<T> IndexMut<Range<usize>> for WaterBuffer<T> {
     fn index_mut(&mut self, idx: Range<usize>) -> &mut Self::Output {
-        if idx.start > idx.end
+        if idx.start > idx.end || idx.end > self.filled_data_length
         {
             panic!("Range out of bounds");
         }
         unsafe { std::slice::from_raw_parts_mut(self.pointer.add(
-            self.start_pos + idx.start), idx.end + self.start_pos) }
+            self.start_pos + idx.start), idx.end - idx.start) }
     }
 }
 
@@ -844,6 +1043,8 @@ impl<T> Index<usize> for WaterBuffer<T> {
         {
             panic!("Index out of bounds");
         }
+        #[cfg(not(feature = "circular_buffer"))]
+        let index = self.start_pos + index;
         unsafe { &*self.pointer.add(index) }
     }
 }
@@ -854,6 +1055,8 @@ impl<T> IndexMut<usize> for WaterBuffer<T> {
         {
             panic!("Index out of bounds");
         }
+        #[cfg(not(feature = "circular_buffer"))]
+        let index = self.start_pos + index;
         unsafe { &mut *self.pointer.add(index) }
     }
 }
@@ -872,35 +1075,3 @@ impl<T> DerefMut for WaterBuffer<T> {
     }
 }
 
-
-#[cfg(feature = "uring")]
-unsafe impl IoBuf for WaterBuffer<u8> {
-    fn stable_ptr(&self) -> *const u8 {
-        // We use the start_pos to ensure we point to the beginning of valid data
-        unsafe { self.pointer.add(self.start_pos) }
-    }
-
-    fn bytes_init(&self) -> usize {
-        self.filled_data_length
-    }
-
-    fn bytes_total(&self) -> usize {
-        // The total capacity relative to the current start_pos
-        self.cap()
-    }
-}
-#[cfg(feature = "uring")]
-
-// IoBufMut for writing data INTO the buffer (reading from a socket)
-unsafe impl IoBufMut for WaterBuffer<u8> {
-    fn stable_mut_ptr(&mut self) -> *mut u8 {
-        // Kernel writes starting after the already filled data
-        unsafe { self.pointer.add(self.start_pos + self.filled_data_length) }
-    }
-
-    unsafe fn set_init(&mut self, pos: usize) {
-        // After kernel writes 'pos' bytes, we update our internal counter
-        self.filled_data_length += pos;
-    }
-}
-
@@ -0,0 +1,275 @@
+//! Length-prefix back-patching.
+//!
+//! When encoding a frame the body length is usually unknown until the body has
+//! been written. [`WaterBuffer::reserve_prefix`] sets aside room for the length at the
+//! current write position and returns a [`PrefixHandle`], [`WaterBuffer::finish_frame`]
+//! later fills in the number of bytes written after the prefix.
+//!
+//! ```
+//! use water_buffer::{Endian, PrefixFormat, WaterBuffer};
+//!
+//! let mut buffer = WaterBuffer::with_capacity(64);
+//! buffer.push(b'Q');
+//! let prefix = buffer.reserve_prefix(PrefixFormat::U32(Endian::Big));
+//! buffer.extend_from_slice(b"SELECT 1\0");
+//! // postgres counts the length field itself
+//! buffer.finish_frame_adjusted(prefix, 4).unwrap();
+//! assert_eq!(&buffer[..5], b"Q\0\0\0\x0d");
+//! ```
+//!
+//! The handle stores a logical offset into the readable window, so the buffer may
+//! grow or compact while the frame is open, but the bytes in front of the prefix
+//! must not be consumed with `advance` before the frame is finished.
+
+use std::fmt;
+use std::ptr;
+
+use super::varint::{uvarint_len, write_uvarint_padded, MAX_VARINT_LEN};
+use super::WaterBuffer;
+
+/// Byte order of fixed width integers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endian {
+    /// most significant byte first (network order)
+    Big,
+    /// least significant byte first
+    Little,
+}
+
+impl Endian {
+    /// reads an unsigned integer of `bytes.len()` bytes (at most 8)
+    #[inline]
+    pub fn read_uint(self, bytes: &[u8]) -> u64 {
+        match self {
+            Endian::Big => bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64),
+            Endian::Little => bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64),
+        }
+    }
+
+    /// writes the low `out.len()` bytes of `value` (at most 8)
+    #[inline]
+    pub fn write_uint(self, value: u64, out: &mut [u8]) {
+        let width = out.len();
+        match self {
+            Endian::Big => out.copy_from_slice(&value.to_be_bytes()[8 - width..]),
+            Endian::Little => out.copy_from_slice(&value.to_le_bytes()[..width]),
+        }
+    }
+}
+
+/// How the length in front of a frame is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrefixFormat {
+    /// single byte
+    U8,
+    /// two bytes
+    U16(Endian),
+    /// three bytes, as used by HTTP/2 frame headers
+    U24(Endian),
+    /// four bytes
+    U32(Endian),
+    /// eight bytes
+    U64(Endian),
+    /// canonical varint with the given number of bytes set aside up front,
+    /// the body is moved once when the final length needs a different size
+    Varint(usize),
+    /// varint padded to exactly the given width, the body never moves
+    VarintFixed(usize),
+}
+
+impl PrefixFormat {
+    /// number of bytes reserved in front of the body
+    pub const fn reserved_len(&self) -> usize {
+        match *self {
+            PrefixFormat::U8 => 1,
+            PrefixFormat::U16(_) => 2,
+            PrefixFormat::U24(_) => 3,
+            PrefixFormat::U32(_) => 4,
+            PrefixFormat::U64(_) => 8,
+            PrefixFormat::Varint(reserved) => reserved,
+            PrefixFormat::VarintFixed(width) => width,
+        }
+    }
+
+    /// largest length this format can hold
+    pub const fn max_len(&self) -> u64 {
+        match *self {
+            PrefixFormat::U8 => u8::MAX as u64,
+            PrefixFormat::U16(_) => u16::MAX as u64,
+            PrefixFormat::U24(_) => (1 << 24) - 1,
+            PrefixFormat::U32(_) => u32::MAX as u64,
+            PrefixFormat::U64(_) | PrefixFormat::Varint(_) => u64::MAX,
+            PrefixFormat::VarintFixed(width) if width >= MAX_VARINT_LEN => u64::MAX,
+            PrefixFormat::VarintFixed(width) => (1 << (7 * width)) - 1,
+        }
+    }
+}
+
+/// Errors returned when a frame length can not be written into its prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixError {
+    /// the length does not fit into the prefix format
+    TooLarge {
+        /// the length that should have been written
+        len: u64,
+        /// the largest length the format can hold
+        max: u64,
+    },
+    /// a negative adjustment made the length negative
+    Negative,
+}
+
+impl fmt::Display for PrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixError::TooLarge { len, max } => {
+                write!(f, "frame length {} does not fit into the prefix (max {})", len, max)
+            }
+            PrefixError::Negative => f.write_str("adjusted frame length is negative"),
+        }
+    }
+}
+
+impl std::error::Error for PrefixError {}
+
+/// Error of [`WaterBuffer::finish_frame`], handing the prefix back so the frame
+/// can still be cancelled or finished after more data was written
+#[derive(Debug, PartialEq, Eq)]
+pub struct FinishFrameError {
+    handle: PrefixHandle,
+    error: PrefixError,
+}
+
+impl FinishFrameError {
+    /// why the length could not be written
+    pub const fn error(&self) -> PrefixError {
+        self.error
+    }
+
+    /// the handle of the still unfinished frame
+    pub fn into_handle(self) -> PrefixHandle {
+        self.handle
+    }
+}
+
+impl fmt::Display for FinishFrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for FinishFrameError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Marks a reserved length prefix, consumed by [`WaterBuffer::finish_frame`]
+#[derive(Debug, PartialEq, Eq)]
+#[must_use = "the prefix stays zeroed unless the frame is finished"]
+pub struct PrefixHandle {
+    offset: usize,
+    format: PrefixFormat,
+}
+
+impl PrefixHandle {
+    /// logical offset of the prefix inside the readable window
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// the format the length will be written in
+    pub const fn format(&self) -> PrefixFormat {
+        self.format
+    }
+}
+
+impl WaterBuffer<u8> {
+    /// Reserves a zeroed length prefix at the current write position.
+    ///
+    /// # Panics
+    /// when a varint format asks for more than [`MAX_VARINT_LEN`] bytes
+    /// or a fixed varint for zero bytes
+    pub fn reserve_prefix(&mut self, format: PrefixFormat) -> PrefixHandle {
+        match format {
+            PrefixFormat::Varint(reserved) => {
+                assert!(reserved <= MAX_VARINT_LEN, "Invalid varint width")
+            }
+            PrefixFormat::VarintFixed(width) => {
+                assert!((1..=MAX_VARINT_LEN).contains(&width), "Invalid varint width")
+            }
+            _ => {}
+        }
+        let reserved = format.reserved_len();
+        let offset = self.len();
+        self.reserve(reserved);
+        self.chunk_mut()[..reserved].fill(0);
+        self.advance_mut(reserved);
+        PrefixHandle { offset, format }
+    }
+
+    /// Writes the number of bytes following the prefix into it and returns that body length
+    pub fn finish_frame(&mut self, handle: PrefixHandle) -> Result<usize, FinishFrameError> {
+        self.finish_frame_adjusted(handle, 0)
+    }
+
+    /// Same as [`WaterBuffer::finish_frame`] but `adjustment` is added to the written length,
+    /// e.g. `+4` when the length counts its own `u32` field,
+    /// or a negative value when header bytes follow the prefix but are not part of the length.
+    /// On error the prefix is left zeroed, the frame stays in the buffer and the handle
+    /// is returned inside the error, e.g. for [`WaterBuffer::cancel_frame`].
+    pub fn finish_frame_adjusted(&mut self, handle: PrefixHandle, adjustment: i64) -> Result<usize, FinishFrameError> {
+        let reserved = handle.format.reserved_len();
+        let body_start = handle.offset + reserved;
+        assert!(body_start <= self.len(), "Frame prefix is out of bounds");
+        let body_len = self.len() - body_start;
+        let value = body_len as i128 + adjustment as i128;
+        if value < 0 {
+            return Err(FinishFrameError { handle, error: PrefixError::Negative });
+        }
+        let max = handle.format.max_len();
+        if value > max as i128 {
+            let error = PrefixError::TooLarge {
+                len: value.min(u64::MAX as i128) as u64,
+                max,
+            };
+            return Err(FinishFrameError { handle, error });
+        }
+        let value = value as u64;
+        let prefix = handle.offset..body_start;
+        match handle.format {
+            PrefixFormat::U8 => self[prefix][0] = value as u8,
+            PrefixFormat::U16(endian)
+            | PrefixFormat::U24(endian)
+            | PrefixFormat::U32(endian)
+            | PrefixFormat::U64(endian) => endian.write_uint(value, &mut self[prefix]),
+            PrefixFormat::VarintFixed(_) => write_uvarint_padded(value, &mut self[prefix]),
+            PrefixFormat::Varint(_) => {
+                let needed = uvarint_len(value);
+                if needed != reserved {
+                    self.move_frame_body(body_start, body_len, needed, reserved);
+                }
+                write_uvarint_padded(value, &mut self[handle.offset..handle.offset + needed]);
+            }
+        }
+        Ok(body_len)
+    }
+
+    /// Drops an unfinished frame, including its prefix, from the buffer
+    pub fn cancel_frame(&mut self, handle: PrefixHandle) {
+        self.truncate(handle.offset);
+    }
+
+    /// moves `body_len` bytes at logical `body_start` so the prefix occupies `needed` bytes instead of `reserved`
+    fn move_frame_body(&mut self, body_start: usize, body_len: usize, needed: usize, reserved: usize) {
+        if needed > reserved {
+            self.reserve(needed - reserved);
+        }
+        let new_body_start = body_start + needed - reserved;
+        unsafe {
+            let base = self.pointer.add(self.start_pos);
+            ptr::copy(base.add(body_start), base.add(new_body_start), body_len);
+        }
+        self.filled_data_length = self.filled_data_length + needed - reserved;
+    }
+}
@@ -0,0 +1,225 @@
+//! Segmented storage for very large payloads.
+//!
+//! Growing a single `WaterBuffer` to hundreds of MB means a `realloc` copy on every
+//! expansion step. [`WaterRope`] appends fixed-size `WaterBuffer` segments instead, so
+//! data is written once and never moved, and `advance` frees leading segments as soon
+//! as they have been consumed.
+//!
+//! Every segment except the last is full, which keeps logical indexing O(1).
+//! Parsers that need a contiguous view of a range spanning segments can use
+//! [`WaterRope::make_contiguous`], which copies only that range into a scratch buffer.
+
+use std::collections::VecDeque;
+use std::ops::{Index, Range};
+
+use super::WaterBuffer;
+
+/// default segment size, 64 KiB
+pub const DEFAULT_SEGMENT_SIZE: usize = 64 * 1024;
+
+/// A buffer made of fixed-size `WaterBuffer` segments
+#[derive(Debug)]
+pub struct WaterRope {
+    segments: VecDeque<WaterBuffer<u8>>,
+    segment_size: usize,
+    len: usize,
+    /// backing storage for ranges `make_contiguous` had to copy
+    scratch: WaterBuffer<u8>,
+}
+
+impl Default for WaterRope {
+    fn default() -> Self {
+        WaterRope::new()
+    }
+}
+
+impl WaterRope {
+    /// creating an empty rope with [`DEFAULT_SEGMENT_SIZE`] segments
+    pub fn new() -> WaterRope {
+        WaterRope::with_segment_size(DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// creating an empty rope with segments of `segment_size` bytes
+    ///
+    /// # Panics
+    /// when `segment_size` is zero
+    pub fn with_segment_size(segment_size: usize) -> WaterRope {
+        assert!(segment_size > 0, "Empty segment size");
+        WaterRope {
+            segments: VecDeque::new(),
+            segment_size,
+            len: 0,
+            scratch: WaterBuffer::with_capacity(0),
+        }
+    }
+
+    /// number of readable bytes
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// returns `true` when there is nothing to read
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returning the size of newly allocated segments
+    pub fn segment_size(&self) -> usize {
+        self.segment_size
+    }
+
+    /// number of allocated segments
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Appends `slice`, filling the last segment and spilling the rest into new segments
+    pub fn extend_from_slice(&mut self, mut slice: &[u8]) {
+        self.len += slice.len();
+        while !slice.is_empty() {
+            let segment = match self.segments.back_mut() {
+                Some(segment) if segment.mut_len() > 0 => segment,
+                _ => {
+                    self.segments.push_back(WaterBuffer::with_capacity(self.segment_size));
+                    self.segments.back_mut().unwrap()
+                }
+            };
+            let n = slice.len().min(segment.mut_len());
+            segment.chunk_mut()[..n].copy_from_slice(&slice[..n]);
+            segment.advance_mut(n);
+            slice = &slice[n..];
+        }
+    }
+
+    /// Consumes `n` bytes, dropping every segment that has been read completely
+    ///
+    /// # Panics
+    /// when `n` is larger than [`WaterRope::len`]
+    pub fn advance(&mut self, mut n: usize) {
+        if n > self.len {
+            panic!("Insufficient space to advance");
+        }
+        self.len -= n;
+        while n > 0 {
+            let front = self.segments.front_mut().unwrap();
+            if n < front.len() {
+                front.advance(n);
+                return;
+            }
+            n -= front.len();
+            self.segments.pop_front();
+        }
+        if self.len == 0 {
+            // the last segment may still be partially writable, start over instead
+            self.segments.clear();
+        }
+    }
+
+    /// Drops all data and frees every segment
+    pub fn clear(&mut self) {
+        self.segments.clear();
+        self.len = 0;
+    }
+
+    /// Returns the segment and offset holding logical byte `index`
+    #[inline]
+    fn locate(&self, index: usize) -> (usize, usize) {
+        let first = self.segments[0].len();
+        if index < first {
+            return (0, index);
+        }
+        let rest = index - first;
+        (1 + rest / self.segment_size, rest % self.segment_size)
+    }
+
+    /// returning the byte at logical `index`
+    pub fn get(&self, index: usize) -> Option<u8> {
+        if index >= self.len {
+            return None;
+        }
+        let (segment, offset) = self.locate(index);
+        Some(self.segments[segment][offset])
+    }
+
+    /// Iterates the readable bytes segment by segment
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        self.segments.iter().map(|segment| &segment[..]).filter(|chunk| !chunk.is_empty())
+    }
+
+    /// Iterates the readable bytes
+    pub fn iter(&self) -> impl Iterator<Item = &u8> {
+        self.chunks().flatten()
+    }
+
+    /// Copies `dst.len()` bytes starting at logical `offset` into `dst`
+    ///
+    /// # Panics
+    /// when the range is out of bounds
+    pub fn copy_to_slice(&self, offset: usize, dst: &mut [u8]) {
+        assert!(offset <= self.len && dst.len() <= self.len - offset, "Range out of bounds");
+        if dst.is_empty() {
+            return;
+        }
+        let (mut segment, mut start) = self.locate(offset);
+        let mut copied = 0;
+        while copied < dst.len() {
+            let chunk = &self.segments[segment][start..];
+            let n = chunk.len().min(dst.len() - copied);
+            dst[copied..copied + n].copy_from_slice(&chunk[..n]);
+            copied += n;
+            segment += 1;
+            start = 0;
+        }
+    }
+
+    /// Returns `range` as one slice.
+    /// A range inside a single segment is borrowed directly,
+    /// a range spanning segments is copied into a scratch buffer owned by the rope.
+    ///
+    /// # Panics
+    /// when the range is out of bounds
+    pub fn make_contiguous(&mut self, range: Range<usize>) -> &[u8] {
+        assert!(range.start <= range.end && range.end <= self.len, "Range out of bounds");
+        if range.is_empty() {
+            return &[];
+        }
+        let (first, start) = self.locate(range.start);
+        let (last, _) = self.locate(range.end - 1);
+        if first == last {
+            return &self.segments[first][start..start + range.len()];
+        }
+        let mut scratch = std::mem::replace(&mut self.scratch, WaterBuffer::with_capacity(0));
+        scratch.clear();
+        scratch.reserve(range.len());
+        self.copy_to_slice(range.start, &mut scratch.chunk_mut()[..range.len()]);
+        scratch.advance_mut(range.len());
+        self.scratch = scratch;
+        &self.scratch[..]
+    }
+}
+
+impl Index<usize> for WaterRope {
+    type Output = u8;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < self.len, "Index out of bounds");
+        let (segment, offset) = self.locate(index);
+        &self.segments[segment][offset]
+    }
+}
+
+impl Extend<u8> for WaterRope {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        for byte in iter {
+            self.extend_from_slice(&[byte]);
+        }
+    }
+}
+
+impl<'a> Extend<&'a u8> for WaterRope {
+    fn extend<I: IntoIterator<Item = &'a u8>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
@@ -0,0 +1,337 @@
+//! Delimiter search over the readable window.
+//!
+//! Parsers spend most of their time looking for `\r\n`, `:` or a frame terminator.
+//! On x86_64 every search runs 32 bytes at a time with AVX2 when the CPU supports it
+//! (detected once at runtime), 16 bytes at a time with SSE2 otherwise, and only the tail
+//! shorter than a vector is scanned byte by byte. Other targets use the scalar versions.
+//!
+//! [`WaterBuffer::find_subslice`] filters candidate positions by comparing the first and
+//! last byte of the needle across a whole vector and only verifies those candidates,
+//! which keeps the common case linear without the setup cost of a Two-Way search.
+
+use super::WaterBuffer;
+
+/// haystacks shorter than this are always scanned byte by byte
+const MIN_SIMD_LEN: usize = 16;
+
+/// sets larger than this fall back to a lookup table in `find_any_of`
+const MAX_SIMD_SET: usize = 8;
+
+/// Position of the first `byte` in `haystack`
+pub(crate) fn find_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+    #[cfg(target_arch = "x86_64")]
+    if haystack.len() >= MIN_SIMD_LEN {
+        return unsafe {
+            if x86::has_avx2() {
+                x86::avx2::find_byte(haystack, byte)
+            } else {
+                x86::sse2::find_byte(haystack, byte)
+            }
+        };
+    }
+    scalar::find_byte(haystack, byte)
+}
+
+/// Position of the last `byte` in `haystack`
+pub(crate) fn rfind_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+    #[cfg(target_arch = "x86_64")]
+    if haystack.len() >= MIN_SIMD_LEN {
+        return unsafe {
+            if x86::has_avx2() {
+                x86::avx2::rfind_byte(haystack, byte)
+            } else {
+                x86::sse2::rfind_byte(haystack, byte)
+            }
+        };
+    }
+    scalar::rfind_byte(haystack, byte)
+}
+
+/// Number of `byte` occurrences in `haystack`
+pub(crate) fn count_byte(haystack: &[u8], byte: u8) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    if haystack.len() >= MIN_SIMD_LEN {
+        return unsafe {
+            if x86::has_avx2() {
+                x86::avx2::count_byte(haystack, byte)
+            } else {
+                x86::sse2::count_byte(haystack, byte)
+            }
+        };
+    }
+    scalar::count_byte(haystack, byte)
+}
+
+/// Position of the first byte of `haystack` contained in `set`
+pub(crate) fn find_any_of(haystack: &[u8], set: &[u8]) -> Option<usize> {
+    match set {
+        [] => return None,
+        [byte] => return find_byte(haystack, *byte),
+        _ => {}
+    }
+    #[cfg(target_arch = "x86_64")]
+    if haystack.len() >= MIN_SIMD_LEN && set.len() <= MAX_SIMD_SET {
+        return unsafe {
+            if x86::has_avx2() {
+                x86::avx2::find_any_of(haystack, set)
+            } else {
+                x86::sse2::find_any_of(haystack, set)
+            }
+        };
+    }
+    scalar::find_any_of(haystack, set)
+}
+
+/// Position of the first occurrence of `needle` in `haystack`, an empty needle matches at `0`
+pub(crate) fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    match needle.len() {
+        0 => return Some(0),
+        1 => return find_byte(haystack, needle[0]),
+        n if n > haystack.len() => return None,
+        _ => {}
+    }
+    #[cfg(target_arch = "x86_64")]
+    if haystack.len() >= MIN_SIMD_LEN + needle.len() {
+        return unsafe {
+            if x86::has_avx2() {
+                x86::avx2::find_subslice(haystack, needle)
+            } else {
+                x86::sse2::find_subslice(haystack, needle)
+            }
+        };
+    }
+    scalar::find_subslice(haystack, needle)
+}
+
+mod scalar {
+    pub(super) fn find_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+        haystack.iter().position(|&b| b == byte)
+    }
+
+    pub(super) fn rfind_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+        haystack.iter().rposition(|&b| b == byte)
+    }
+
+    pub(super) fn count_byte(haystack: &[u8], byte: u8) -> usize {
+        haystack.iter().filter(|&&b| b == byte).count()
+    }
+
+    pub(super) fn find_any_of(haystack: &[u8], set: &[u8]) -> Option<usize> {
+        let mut table = [false; 256];
+        for &byte in set {
+            table[byte as usize] = true;
+        }
+        haystack.iter().position(|&b| table[b as usize])
+    }
+
+    /// `needle` holds at least two bytes
+    pub(super) fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        let (&first, rest) = needle.split_first()?;
+        let mut from = 0;
+        while haystack.len() - from >= needle.len() {
+            let pos = from + find_byte(&haystack[from..=haystack.len() - needle.len()], first)?;
+            if &haystack[pos + 1..pos + needle.len()] == rest {
+                return Some(pos);
+            }
+            from = pos + 1;
+        }
+        None
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    #[inline]
+    pub(super) fn has_avx2() -> bool {
+        std::is_x86_feature_detected!("avx2")
+    }
+
+    /// Generates the vector searches for one instruction set.
+    /// Every function expects `haystack` to hold at least one full vector.
+    macro_rules! simd_search {
+        ($name:ident, $feature:literal, $lanes:literal, $vector:ty,
+         $set1:ident, $load:ident, $cmpeq:ident, $or:ident, $and:ident, $movemask:ident) => {
+            pub(super) mod $name {
+                use super::super::scalar;
+                #[allow(unused_imports)]
+                use std::arch::x86_64::*;
+
+                const LANES: usize = $lanes;
+
+                #[inline(always)]
+                unsafe fn mask(haystack: &[u8], at: usize, needle: $vector) -> u32 {
+                    unsafe {
+                        let chunk = $load(haystack.as_ptr().add(at) as *const $vector);
+                        $movemask($cmpeq(chunk, needle)) as u32
+                    }
+                }
+
+                #[target_feature(enable = $feature)]
+                pub(in super::super) unsafe fn find_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+                    unsafe {
+                        let needle = $set1(byte as i8);
+                        let mut at = 0;
+                        while at + LANES <= haystack.len() {
+                            let found = mask(haystack, at, needle);
+                            if found != 0 {
+                                return Some(at + found.trailing_zeros() as usize);
+                            }
+                            at += LANES;
+                        }
+                        scalar::find_byte(&haystack[at..], byte).map(|pos| at + pos)
+                    }
+                }
+
+                #[target_feature(enable = $feature)]
+                pub(in super::super) unsafe fn rfind_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+                    unsafe {
+                        let needle = $set1(byte as i8);
+                        let mut end = haystack.len();
+                        while end >= LANES {
+                            let at = end - LANES;
+                            let found = mask(haystack, at, needle);
+                            if found != 0 {
+                                return Some(at + 31 - found.leading_zeros() as usize);
+                            }
+                            end = at;
+                        }
+                        scalar::rfind_byte(&haystack[..end], byte)
+                    }
+                }
+
+                #[target_feature(enable = $feature)]
+                pub(in super::super) unsafe fn count_byte(haystack: &[u8], byte: u8) -> usize {
+                    unsafe {
+                        let needle = $set1(byte as i8);
+                        let mut count = 0;
+                        let mut at = 0;
+                        while at + LANES <= haystack.len() {
+                            count += mask(haystack, at, needle).count_ones() as usize;
+                            at += LANES;
+                        }
+                        count + scalar::count_byte(&haystack[at..], byte)
+                    }
+                }
+
+                /// `set` holds between two and `MAX_SIMD_SET` bytes
+                #[target_feature(enable = $feature)]
+                pub(in super::super) unsafe fn find_any_of(haystack: &[u8], set: &[u8]) -> Option<usize> {
+                    unsafe {
+                        let mut needles = [$set1(0); super::super::MAX_SIMD_SET];
+                        for (needle, &byte) in needles.iter_mut().zip(set) {
+                            *needle = $set1(byte as i8);
+                        }
+                        let needles = &needles[..set.len()];
+                        let mut at = 0;
+                        while at + LANES <= haystack.len() {
+                            let chunk = $load(haystack.as_ptr().add(at) as *const $vector);
+                            let mut hits = $cmpeq(chunk, needles[0]);
+                            for &needle in &needles[1..] {
+                                hits = $or(hits, $cmpeq(chunk, needle));
+                            }
+                            let found = $movemask(hits) as u32;
+                            if found != 0 {
+                                return Some(at + found.trailing_zeros() as usize);
+                            }
+                            at += LANES;
+                        }
+                        scalar::find_any_of(&haystack[at..], set).map(|pos| at + pos)
+                    }
+                }
+
+                /// `needle` holds at least two bytes and `haystack` at least one vector more
+                #[target_feature(enable = $feature)]
+                pub(in super::super) unsafe fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+                    unsafe {
+                        let last = needle.len() - 1;
+                        let first_byte = $set1(needle[0] as i8);
+                        let last_byte = $set1(needle[last] as i8);
+                        let mut at = 0;
+                        while at + last + LANES <= haystack.len() {
+                            let head = $load(haystack.as_ptr().add(at) as *const $vector);
+                            let tail = $load(haystack.as_ptr().add(at + last) as *const $vector);
+                            let mut candidates =
+                                $movemask($and($cmpeq(head, first_byte), $cmpeq(tail, last_byte))) as u32;
+                            while candidates != 0 {
+                                let pos = at + candidates.trailing_zeros() as usize;
+                                if &haystack[pos + 1..pos + last] == &needle[1..last] {
+                                    return Some(pos);
+                                }
+                                candidates &= candidates - 1;
+                            }
+                            at += LANES;
+                        }
+                        scalar::find_subslice(&haystack[at..], needle).map(|pos| at + pos)
+                    }
+                }
+            }
+        };
+    }
+
+    simd_search!(sse2, "sse2", 16, __m128i,
+        _mm_set1_epi8, _mm_loadu_si128, _mm_cmpeq_epi8, _mm_or_si128, _mm_and_si128, _mm_movemask_epi8);
+    simd_search!(avx2, "avx2", 32, __m256i,
+        _mm256_set1_epi8, _mm256_loadu_si256, _mm256_cmpeq_epi8, _mm256_or_si256, _mm256_and_si256,
+        _mm256_movemask_epi8);
+}
+
+/// Iterator over the start positions of non-overlapping matches,
+/// created by [`WaterBuffer::find_iter`]
+#[derive(Debug, Clone)]
+pub struct Matches<'a, 'n> {
+    haystack: &'a [u8],
+    needle: &'n [u8],
+    pos: usize,
+}
+
+impl Iterator for Matches<'_, '_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.pos > self.haystack.len() {
+            return None;
+        }
+        let found = self.pos + find_subslice(&self.haystack[self.pos..], self.needle)?;
+        // an empty needle matches at every position, step over it
+        self.pos = found + self.needle.len().max(1);
+        Some(found)
+    }
+}
+
+impl std::iter::FusedIterator for Matches<'_, '_> {}
+
+impl WaterBuffer<u8> {
+    /// returning the position of the first `byte` in the readable window
+    pub fn find_byte(&self, byte: u8) -> Option<usize> {
+        find_byte(self.chunk(), byte)
+    }
+
+    /// returning the position of the last `byte` in the readable window
+    pub fn rfind(&self, byte: u8) -> Option<usize> {
+        rfind_byte(self.chunk(), byte)
+    }
+
+    /// returning the position of the first byte that is contained in `set`
+    pub fn find_any_of(&self, set: &[u8]) -> Option<usize> {
+        find_any_of(self.chunk(), set)
+    }
+
+    /// returning the position of the first occurrence of `needle`, an empty needle matches at `0`
+    pub fn find_subslice(&self, needle: &[u8]) -> Option<usize> {
+        find_subslice(self.chunk(), needle)
+    }
+
+    /// counting the occurrences of `byte` in the readable window
+    pub fn count_byte(&self, byte: u8) -> usize {
+        count_byte(self.chunk(), byte)
+    }
+
+    /// Iterates the positions of the non-overlapping occurrences of `needle`
+    pub fn find_iter<'a, 'n>(&'a self, needle: &'n [u8]) -> Matches<'a, 'n> {
+        Matches {
+            haystack: self.chunk(),
+            needle,
+            pos: 0,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Serde support, enabled with the `serde` feature.
+//!
+//! `WaterBuffer<u8>` is serialized as a byte string through `serialize_bytes`.
+//! Deserialization writes straight into a `WaterBuffer`: borrowed bytes are copied once,
+//! owned byte buffers are adopted without copying, and formats that encode bytes
+//! as a sequence of integers are handled through `visit_seq`.
+
+use std::fmt;
+
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::WaterBuffer;
+
+impl Serialize for WaterBuffer<u8> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.as_slices() {
+            (first, []) => serializer.serialize_bytes(first),
+            (first, second) => serializer.serialize_bytes(&[first, second].concat()),
+        }
+    }
+}
+
+struct WaterBufferVisitor;
+
+impl<'de> Visitor<'de> for WaterBufferVisitor {
+    type Value = WaterBuffer<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(WaterBuffer::from(v))
+    }
+
+    fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(WaterBuffer::from_vec(v))
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(WaterBuffer::from(v))
+    }
+
+    fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(WaterBuffer::from_vec(v.into_bytes()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // never trust the hint blindly, it comes from the input
+        let mut buffer = WaterBuffer::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            buffer.push(byte);
+        }
+        Ok(buffer)
+    }
+}
+
+impl<'de> Deserialize<'de> for WaterBuffer<u8> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(WaterBufferVisitor)
+    }
+}
@@ -0,0 +1,75 @@
+//! Idle shrinking for long-lived buffers.
+//!
+//! A connection buffer grows to fit the largest message it has seen and keeps
+//! that capacity forever. [`IdleShrink`] lets the owner (or a pool) call
+//! [`IdleShrink::tick`] periodically and give memory back once the buffer has
+//! been sitting empty for a while.
+
+use super::WaterBuffer;
+
+/// Describes when an idle buffer should give memory back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShrinkPolicy {
+    /// capacity the buffer is allowed to keep while idle
+    pub retain_capacity: usize,
+    /// number of consecutive idle ticks before shrinking
+    pub idle_ticks: u32,
+}
+
+impl ShrinkPolicy {
+    /// creating a new policy which keeps `retain_capacity` bytes after `idle_ticks` idle ticks
+    pub const fn new(retain_capacity: usize, idle_ticks: u32) -> ShrinkPolicy {
+        ShrinkPolicy {
+            retain_capacity,
+            idle_ticks,
+        }
+    }
+}
+
+impl Default for ShrinkPolicy {
+    fn default() -> Self {
+        ShrinkPolicy::new(8 * 1024, 4)
+    }
+}
+
+/// Tracks how long a buffer has been idle and shrinks it according to a [`ShrinkPolicy`]
+#[derive(Debug, Clone)]
+pub struct IdleShrink {
+    policy: ShrinkPolicy,
+    idle: u32,
+}
+
+impl IdleShrink {
+    /// creating a new tracker for the given policy
+    pub const fn new(policy: ShrinkPolicy) -> IdleShrink {
+        IdleShrink { policy, idle: 0 }
+    }
+
+    /// returning the policy used by this tracker
+    pub const fn policy(&self) -> &ShrinkPolicy {
+        &self.policy
+    }
+
+    /// Should be called periodically by the owner of the buffer.
+    /// A tick where the buffer holds unread data resets the idle counter,
+    /// returns `true` when the buffer was shrunk.
+    pub fn tick(&mut self, buffer: &mut WaterBuffer<u8>) -> bool {
+        if !buffer.is_empty() || buffer.cap() <= self.policy.retain_capacity {
+            self.idle = 0;
+            return false;
+        }
+        self.idle += 1;
+        if self.idle < self.policy.idle_ticks {
+            return false;
+        }
+        self.idle = 0;
+        buffer.shrink_to(self.policy.retain_capacity);
+        true
+    }
+
+    /// Marks the buffer as active, e.g. after a read or write
+    #[inline]
+    pub const fn touch(&mut self) {
+        self.idle = 0;
+    }
+}
@@ -0,0 +1,93 @@
+//! Blocking and readiness based IO with `std::io::Read` / `Write`.
+//!
+//! Reads go into the spare capacity after the readable window and are committed with
+//! `advance_mut`, writes consume the readable window with `advance`.
+//! `Read` only accepts initialized bytes, so spare capacity that was never initialized is
+//! zeroed the first time it is handed out, later reads reuse it without zeroing again.
+//! `Interrupted` is retried, `WouldBlock` is handed back to the caller with everything
+//! transferred so far kept in the buffer, so the same calls work on non-blocking
+//! descriptors driven by epoll.
+
+use std::io::{self, Read, Write};
+
+use super::WaterBuffer;
+
+/// spare capacity reserved per read by [`WaterBuffer::read_to_end_from`]
+const DEFAULT_READ_HINT: usize = 8 * 1024;
+
+impl WaterBuffer<u8> {
+    /// Reads once from `reader` into the spare capacity, reserving at least `hint` bytes first.
+    /// Returns the number of bytes read, `0` meaning EOF.
+    pub fn read_from<R: Read + ?Sized>(&mut self, reader: &mut R, hint: usize) -> io::Result<usize> {
+        self.read_from_limited(reader, hint, usize::MAX)
+    }
+
+    /// Same as [`WaterBuffer::read_from`], asking the reader for at most `limit` bytes
+    fn read_from_limited<R: Read + ?Sized>(&mut self, reader: &mut R, hint: usize, limit: usize) -> io::Result<usize> {
+        self.reserve(hint.max(1));
+        loop {
+            // `Read` only accepts initialized bytes, only never initialized ones are zeroed
+            let target = self.init_spare_mut(limit);
+            let len = target.len();
+            match reader.read(target) {
+                Ok(n) => {
+                    assert!(n <= len, "reader returned more bytes than it was given");
+                    self.advance_mut(n);
+                    return Ok(n);
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Reads from `reader` until EOF, appending to the buffer.
+    /// Fails with `InvalidData` once the readable length of the buffer exceeds `max`,
+    /// so the limit also holds across calls repeated after `WouldBlock`,
+    /// which keeps the bytes read so far in the buffer.
+    /// Returns the number of bytes read in this call.
+    pub fn read_to_end_from<R: Read + ?Sized>(&mut self, reader: &mut R, max: usize) -> io::Result<usize> {
+        let mut total = 0usize;
+        loop {
+            if self.len() > max {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "stream exceeds the size limit"));
+            }
+            // ask for one byte more than allowed so an oversized stream is detected
+            let allowance = (max - self.len()).saturating_add(1);
+            let hint = allowance.min(DEFAULT_READ_HINT);
+            match self.read_from_limited(reader, hint, allowance)? {
+                0 => return Ok(total),
+                n => total += n,
+            }
+        }
+    }
+
+    /// Writes once from the readable window and advances past the written bytes.
+    /// Returns the number of bytes written, `0` when the buffer is empty.
+    pub fn write_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> io::Result<usize> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match writer.write(self.chunk()) {
+                Ok(n) => {
+                    self.advance(n);
+                    return Ok(n);
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Writes the whole readable window, advancing past every partial write.
+    /// On `WouldBlock` the unwritten bytes stay in the buffer.
+    pub fn write_all_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> io::Result<()> {
+        while !self.is_empty() {
+            if self.write_to(writer)? == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+        }
+        Ok(())
+    }
+}
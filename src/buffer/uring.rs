@@ -0,0 +1,159 @@
+//! io_uring buffer integration, enabled with the `uring` feature.
+//!
+//! tokio-uring takes ownership of a buffer for the duration of an operation and
+//! describes it with three numbers measured from `stable_ptr`: the initialized length
+//! (`bytes_init`), the total length (`bytes_total`) and, after a read, the absolute
+//! initialized length passed to `set_init`. All three use the same origin here:
+//!
+//! * `WaterBuffer<u8>` itself behaves like a `Vec<u8>` over its readable window, a read
+//!   overwrites from the first readable byte and a write sends the readable window.
+//! * [`WaterReadSlice`] starts at the write position, so a read appends to the buffer.
+//! * [`WaterWriteSlice`] covers exactly the readable window and is never read into.
+
+use tokio_uring::buf::{IoBuf, IoBufMut};
+
+use super::WaterBuffer;
+
+unsafe impl IoBuf for WaterBuffer<u8> {
+    fn stable_ptr(&self) -> *const u8 {
+        unsafe { self.pointer.add(self.start_pos) }
+    }
+
+    fn bytes_init(&self) -> usize {
+        self.filled_data_length
+    }
+
+    fn bytes_total(&self) -> usize {
+        self.cap - self.start_pos
+    }
+}
+
+unsafe impl IoBufMut for WaterBuffer<u8> {
+    fn stable_mut_ptr(&mut self) -> *mut u8 {
+        unsafe { self.pointer.add(self.start_pos) }
+    }
+
+    unsafe fn set_init(&mut self, pos: usize) {
+        // `pos` is absolute from `stable_mut_ptr`, never shrink the readable window
+        if self.filled_data_length < pos {
+            self.filled_data_length = pos;
+        }
+    }
+}
+
+/// Owned view over the spare capacity of a `WaterBuffer`, used to read from io_uring.
+/// Bytes read by the kernel are appended after the bytes already in the buffer.
+#[derive(Debug)]
+pub struct WaterReadSlice {
+    buffer: WaterBuffer<u8>,
+    /// readable length when the slice was created, the slice starts right after it
+    base: usize,
+}
+
+impl WaterReadSlice {
+    /// creating a read slice over the current spare capacity of `buffer`
+    pub fn new(buffer: WaterBuffer<u8>) -> WaterReadSlice {
+        let base = buffer.filled_data_length;
+        WaterReadSlice { buffer, base }
+    }
+
+    /// returning the buffer, including any bytes read into the slice
+    pub fn buffer(&self) -> &WaterBuffer<u8> {
+        &self.buffer
+    }
+
+    /// returning the buffer, including any bytes read into the slice
+    pub fn into_inner(self) -> WaterBuffer<u8> {
+        self.buffer
+    }
+}
+
+impl From<WaterBuffer<u8>> for WaterReadSlice {
+    fn from(buffer: WaterBuffer<u8>) -> Self {
+        WaterReadSlice::new(buffer)
+    }
+}
+
+unsafe impl IoBuf for WaterReadSlice {
+    fn stable_ptr(&self) -> *const u8 {
+        unsafe { self.buffer.pointer.add(self.buffer.start_pos + self.base) }
+    }
+
+    fn bytes_init(&self) -> usize {
+        self.buffer.filled_data_length - self.base
+    }
+
+    fn bytes_total(&self) -> usize {
+        self.buffer.cap - self.buffer.start_pos - self.base
+    }
+}
+
+unsafe impl IoBufMut for WaterReadSlice {
+    fn stable_mut_ptr(&mut self) -> *mut u8 {
+        unsafe { self.buffer.pointer.add(self.buffer.start_pos + self.base) }
+    }
+
+    unsafe fn set_init(&mut self, pos: usize) {
+        let filled = self.base + pos;
+        if self.buffer.filled_data_length < filled {
+            self.buffer.filled_data_length = filled;
+        }
+    }
+}
+
+/// Owned view over the readable window of a `WaterBuffer`, used to write to io_uring.
+/// The buffer is left untouched, `advance` it by the number of bytes written afterwards.
+#[derive(Debug)]
+pub struct WaterWriteSlice {
+    buffer: WaterBuffer<u8>,
+}
+
+impl WaterWriteSlice {
+    /// creating a write slice over the readable window of `buffer`
+    pub fn new(buffer: WaterBuffer<u8>) -> WaterWriteSlice {
+        WaterWriteSlice { buffer }
+    }
+
+    /// returning the buffer
+    pub fn buffer(&self) -> &WaterBuffer<u8> {
+        &self.buffer
+    }
+
+    /// returning the buffer
+    pub fn into_inner(self) -> WaterBuffer<u8> {
+        self.buffer
+    }
+}
+
+impl From<WaterBuffer<u8>> for WaterWriteSlice {
+    fn from(buffer: WaterBuffer<u8>) -> Self {
+        WaterWriteSlice::new(buffer)
+    }
+}
+
+unsafe impl IoBuf for WaterWriteSlice {
+    fn stable_ptr(&self) -> *const u8 {
+        unsafe { self.buffer.pointer.add(self.buffer.start_pos) }
+    }
+
+    fn bytes_init(&self) -> usize {
+        self.buffer.filled_data_length
+    }
+
+    fn bytes_total(&self) -> usize {
+        self.buffer.filled_data_length
+    }
+}
+
+impl WaterBuffer<u8> {
+    /// Converts the buffer into a [`WaterReadSlice`] with at least `additional` bytes of spare capacity
+    pub fn into_read_slice(mut self, additional: usize) -> WaterReadSlice {
+        self.reserve(additional);
+        WaterReadSlice::new(self)
+    }
+
+    /// Converts the buffer into a [`WaterWriteSlice`] over its readable window
+    pub fn into_write_slice(self) -> WaterWriteSlice {
+        WaterWriteSlice::new(self)
+    }
+}
@@ -0,0 +1,144 @@
+//! LEB128 varints (protobuf style) with zigzag encoding for signed values.
+//!
+//! Encoders write straight into the spare capacity returned by `chunk_mut`,
+//! decoders read from the readable window and only `advance` once a full
+//! varint has been decoded, so a truncated varint can be retried after more
+//! bytes arrive.
+
+use std::fmt;
+
+use super::WaterBuffer;
+
+/// the longest encoding of a `u64`
+pub const MAX_VARINT_LEN: usize = 10;
+
+/// Errors returned while decoding or encoding varints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarintError {
+    /// the readable bytes end in the middle of a varint, nothing was consumed
+    Truncated,
+    /// the varint does not fit into 64 bits (or into the requested fixed width)
+    Overflow,
+}
+
+impl fmt::Display for VarintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarintError::Truncated => f.write_str("varint is truncated"),
+            VarintError::Overflow => f.write_str("varint overflows 64 bits"),
+        }
+    }
+}
+
+impl std::error::Error for VarintError {}
+
+/// maps signed integers to unsigned so small negative numbers stay short
+#[inline(always)]
+pub const fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// reverses [`zigzag_encode`]
+#[inline(always)]
+pub const fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// returns how many bytes `value` takes as a varint
+#[inline(always)]
+pub const fn uvarint_len(value: u64) -> usize {
+    let bits = 64 - (value | 1).leading_zeros() as usize;
+    bits.div_ceil(7)
+}
+
+/// writes `value` as a varint padded to exactly `out.len()` bytes,
+/// callers make sure `uvarint_len(value) <= out.len()`
+#[cfg(not(feature = "circular_buffer"))]
+#[inline(always)]
+pub(crate) fn write_uvarint_padded(mut value: u64, out: &mut [u8]) {
+    let last = out.len() - 1;
+    for byte in out[..last].iter_mut() {
+        *byte = (value as u8) | 0x80;
+        value >>= 7;
+    }
+    out[last] = value as u8;
+}
+
+impl WaterBuffer<u8> {
+    #[cfg(not(feature = "circular_buffer"))]
+    /// Appends `value` as an unsigned LEB128 varint and returns the number of bytes written
+    #[inline]
+    pub fn put_uvarint(&mut self, mut value: u64) -> usize {
+        self.reserve(MAX_VARINT_LEN);
+        let out = self.chunk_mut();
+        let mut written = 0;
+        while value >= 0x80 {
+            out[written] = (value as u8) | 0x80;
+            value >>= 7;
+            written += 1;
+        }
+        out[written] = value as u8;
+        written += 1;
+        self.advance_mut(written);
+        written
+    }
+
+    #[cfg(not(feature = "circular_buffer"))]
+    /// Appends `value` zigzag encoded as a varint and returns the number of bytes written
+    #[inline]
+    pub fn put_ivarint(&mut self, value: i64) -> usize {
+        self.put_uvarint(zigzag_encode(value))
+    }
+
+    #[cfg(not(feature = "circular_buffer"))]
+    /// Appends `value` as a varint padded to exactly `width` bytes.
+    /// Padding uses continuation bytes (`0x80`), so any varint decoder reads the same value,
+    /// this is useful for length fields that get back-patched later.
+    ///
+    /// # Panics
+    /// when `width` is zero or larger than [`MAX_VARINT_LEN`]
+    pub fn put_uvarint_fixed(&mut self, value: u64, width: usize) -> Result<(), VarintError> {
+        assert!((1..=MAX_VARINT_LEN).contains(&width), "Invalid varint width");
+        if uvarint_len(value) > width {
+            return Err(VarintError::Overflow);
+        }
+        self.reserve(width);
+        write_uvarint_padded(value, &mut self.chunk_mut()[..width]);
+        self.advance_mut(width);
+        Ok(())
+    }
+
+    /// Decodes an unsigned varint at the start of the readable window without consuming it.
+    /// Returns the value and the number of bytes it occupies.
+    pub fn peek_uvarint(&self) -> Result<(u64, usize), VarintError> {
+        let (first, second) = self.as_slices();
+        let mut value = 0u64;
+        for (i, &byte) in first.iter().chain(second).take(MAX_VARINT_LEN).enumerate() {
+            if i == MAX_VARINT_LEN - 1 && byte > 1 {
+                return Err(VarintError::Overflow);
+            }
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte < 0x80 {
+                return Ok((value, i + 1));
+            }
+        }
+        if self.len() >= MAX_VARINT_LEN {
+            return Err(VarintError::Overflow);
+        }
+        Err(VarintError::Truncated)
+    }
+
+    /// Decodes and consumes an unsigned varint.
+    /// Nothing is consumed when an error is returned.
+    pub fn get_uvarint(&mut self) -> Result<u64, VarintError> {
+        let (value, len) = self.peek_uvarint()?;
+        self.advance(len);
+        Ok(value)
+    }
+
+    /// Decodes and consumes a zigzag encoded signed varint.
+    /// Nothing is consumed when an error is returned.
+    pub fn get_ivarint(&mut self) -> Result<i64, VarintError> {
+        self.get_uvarint().map(zigzag_decode)
+    }
+}
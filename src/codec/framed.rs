@@ -0,0 +1,360 @@
+//! Async framing on top of tokio's `AsyncRead` / `AsyncWrite`, enabled with the `tokio` feature.
+//!
+//! tokio-util's `Framed` is hard-wired to `BytesMut`, the types here do the same job with
+//! `WaterBuffer` read and write buffers:
+//!
+//! * [`WaterFramedRead`] reads into the uninitialized tail of the read buffer
+//!   (`spare_capacity_mut` + `advance_mut`, nothing is zeroed) and drives a [`WaterDecoder`].
+//! * [`WaterFramedWrite`] encodes items with a [`WaterEncoder`] into a write buffer and
+//!   flushes it, applying backpressure once the buffer reaches a configurable size.
+//! * [`WaterFramed`] does both over a single duplex transport.
+//!
+//! The transport must be `Unpin`, which holds for tokio's sockets, pipes and duplex streams.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::{WaterDecoder, WaterEncoder};
+use crate::async_io::tokio_ext::{poll_read_water, poll_write_all_water};
+use crate::WaterBuffer;
+
+/// capacity of freshly created read and write buffers
+const INITIAL_CAPACITY: usize = 8 * 1024;
+
+/// write buffer length at which `poll_ready` flushes before accepting more items
+pub const DEFAULT_BACKPRESSURE_BOUNDARY: usize = INITIAL_CAPACITY;
+
+/// Read side bookkeeping shared by [`WaterFramedRead`] and [`WaterFramed`]
+#[derive(Debug, Default)]
+struct ReadState {
+    /// the transport returned EOF on the last read
+    eof: bool,
+    /// the read buffer may hold a frame the decoder has not seen yet
+    is_readable: bool,
+    /// the last poll returned an error, the stream ends on the next poll
+    has_errored: bool,
+}
+
+/// Decodes the next frame, reading more from `io` whenever the decoder needs it
+fn poll_next_frame<T: AsyncRead + Unpin, D: WaterDecoder>(
+    io: &mut T,
+    cx: &mut Context<'_>,
+    decoder: &mut D,
+    buffer: &mut WaterBuffer<u8>,
+    state: &mut ReadState,
+) -> Poll<Option<Result<D::Item, D::Error>>> {
+    loop {
+        if state.has_errored {
+            state.is_readable = false;
+            state.has_errored = false;
+            return Poll::Ready(None);
+        }
+        if state.is_readable {
+            let decoded = if state.eof {
+                decoder.decode_eof(buffer)
+            } else {
+                decoder.decode(buffer)
+            };
+            match decoded {
+                Ok(Some(frame)) => return Poll::Ready(Some(Ok(frame))),
+                Ok(None) if state.eof => {
+                    state.is_readable = false;
+                    return Poll::Ready(None);
+                }
+                Ok(None) => state.is_readable = false,
+                Err(error) => {
+                    state.has_errored = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+            }
+        }
+        match ready!(poll_read_water(Pin::new(&mut *io), cx, buffer)) {
+            Ok(0) if state.eof => return Poll::Ready(None),
+            Ok(0) => state.eof = true,
+            Ok(_) => state.eof = false,
+            Err(error) => {
+                state.has_errored = true;
+                return Poll::Ready(Some(Err(error.into())));
+            }
+        }
+        state.is_readable = true;
+    }
+}
+
+/// Writes the whole of `buffer` to `io`, advancing past every partial write, then flushes `io`
+fn poll_flush_buffer<T: AsyncWrite + Unpin>(
+    io: &mut T,
+    cx: &mut Context<'_>,
+    buffer: &mut WaterBuffer<u8>,
+) -> Poll<io::Result<()>> {
+    ready!(poll_write_all_water(Pin::new(&mut *io), cx, buffer))?;
+    buffer.clear();
+    Pin::new(io).poll_flush(cx)
+}
+
+/// A `Stream` of frames decoded from an `AsyncRead`
+#[derive(Debug)]
+pub struct WaterFramedRead<T, D> {
+    inner: T,
+    decoder: D,
+    buffer: WaterBuffer<u8>,
+    state: ReadState,
+}
+
+impl<T, D> WaterFramedRead<T, D> {
+    /// creating a framed reader with the default read buffer capacity
+    pub fn new(inner: T, decoder: D) -> Self {
+        Self::with_capacity(inner, decoder, INITIAL_CAPACITY)
+    }
+
+    /// creating a framed reader with a read buffer of `capacity` bytes
+    pub fn with_capacity(inner: T, decoder: D, capacity: usize) -> Self {
+        WaterFramedRead {
+            inner,
+            decoder,
+            buffer: WaterBuffer::with_capacity(capacity),
+            state: ReadState::default(),
+        }
+    }
+
+    /// returning the underlying reader
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// returning the underlying reader mutably, reading from it directly corrupts the frame stream
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// consuming the framed reader, any buffered bytes are lost
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// returning the decoder
+    pub fn decoder(&self) -> &D {
+        &self.decoder
+    }
+
+    /// returning the decoder mutably
+    pub fn decoder_mut(&mut self) -> &mut D {
+        &mut self.decoder
+    }
+
+    /// returning the bytes read but not decoded yet
+    pub fn read_buffer(&self) -> &WaterBuffer<u8> {
+        &self.buffer
+    }
+
+    /// returning the read buffer mutably
+    pub fn read_buffer_mut(&mut self) -> &mut WaterBuffer<u8> {
+        &mut self.buffer
+    }
+}
+
+impl<T: AsyncRead + Unpin, D: WaterDecoder + Unpin> Stream for WaterFramedRead<T, D> {
+    type Item = Result<D::Item, D::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        poll_next_frame(&mut this.inner, cx, &mut this.decoder, &mut this.buffer, &mut this.state)
+    }
+}
+
+/// A `Sink` encoding items into a `WaterBuffer` and writing them to an `AsyncWrite`
+#[derive(Debug)]
+pub struct WaterFramedWrite<T, E> {
+    inner: T,
+    encoder: E,
+    buffer: WaterBuffer<u8>,
+    backpressure_boundary: usize,
+}
+
+impl<T, E> WaterFramedWrite<T, E> {
+    /// creating a framed writer
+    pub fn new(inner: T, encoder: E) -> Self {
+        WaterFramedWrite {
+            inner,
+            encoder,
+            buffer: WaterBuffer::with_capacity(INITIAL_CAPACITY),
+            backpressure_boundary: DEFAULT_BACKPRESSURE_BOUNDARY,
+        }
+    }
+
+    /// returning the underlying writer
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// returning the underlying writer mutably, writing to it directly corrupts the frame stream
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// consuming the framed writer, unflushed bytes are lost
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// returning the encoder
+    pub fn encoder(&self) -> &E {
+        &self.encoder
+    }
+
+    /// returning the encoder mutably
+    pub fn encoder_mut(&mut self) -> &mut E {
+        &mut self.encoder
+    }
+
+    /// returning the encoded bytes not written yet
+    pub fn write_buffer(&self) -> &WaterBuffer<u8> {
+        &self.buffer
+    }
+
+    /// returning the write buffer mutably
+    pub fn write_buffer_mut(&mut self) -> &mut WaterBuffer<u8> {
+        &mut self.buffer
+    }
+
+    /// returning the write buffer length at which `poll_ready` flushes first
+    pub fn backpressure_boundary(&self) -> usize {
+        self.backpressure_boundary
+    }
+
+    /// setting the write buffer length at which `poll_ready` flushes first
+    pub fn set_backpressure_boundary(&mut self, boundary: usize) {
+        self.backpressure_boundary = boundary;
+    }
+}
+
+impl<T: AsyncWrite + Unpin, E: WaterEncoder<I> + Unpin, I> Sink<I> for WaterFramedWrite<T, E> {
+    type Error = E::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.buffer.len() >= self.backpressure_boundary {
+            return self.poll_flush(cx);
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: I) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        this.encoder.encode(item, &mut this.buffer)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        poll_flush_buffer(&mut this.inner, cx, &mut this.buffer).map_err(Into::into)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx).map_err(Into::into)
+    }
+}
+
+/// A duplex transport framed by a single codec, both a `Stream` and a `Sink`
+#[derive(Debug)]
+pub struct WaterFramed<T, C> {
+    inner: T,
+    codec: C,
+    read_buffer: WaterBuffer<u8>,
+    write_buffer: WaterBuffer<u8>,
+    state: ReadState,
+    backpressure_boundary: usize,
+}
+
+impl<T, C> WaterFramed<T, C> {
+    /// creating a framed transport
+    pub fn new(inner: T, codec: C) -> Self {
+        WaterFramed {
+            inner,
+            codec,
+            read_buffer: WaterBuffer::with_capacity(INITIAL_CAPACITY),
+            write_buffer: WaterBuffer::with_capacity(INITIAL_CAPACITY),
+            state: ReadState::default(),
+            backpressure_boundary: DEFAULT_BACKPRESSURE_BOUNDARY,
+        }
+    }
+
+    /// returning the underlying transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// returning the underlying transport mutably
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// consuming the framed transport, buffered bytes are lost
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// returning the codec
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// returning the codec mutably
+    pub fn codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// returning the bytes read but not decoded yet
+    pub fn read_buffer(&self) -> &WaterBuffer<u8> {
+        &self.read_buffer
+    }
+
+    /// returning the encoded bytes not written yet
+    pub fn write_buffer(&self) -> &WaterBuffer<u8> {
+        &self.write_buffer
+    }
+
+    /// setting the write buffer length at which `poll_ready` flushes first
+    pub fn set_backpressure_boundary(&mut self, boundary: usize) {
+        self.backpressure_boundary = boundary;
+    }
+}
+
+impl<T: AsyncRead + Unpin, C: WaterDecoder + Unpin> Stream for WaterFramed<T, C> {
+    type Item = Result<C::Item, C::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        poll_next_frame(&mut this.inner, cx, &mut this.codec, &mut this.read_buffer, &mut this.state)
+    }
+}
+
+impl<T: AsyncWrite + Unpin, C: WaterEncoder<I> + Unpin, I> Sink<I> for WaterFramed<T, C> {
+    type Error = C::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.write_buffer.len() >= self.backpressure_boundary {
+            return self.poll_flush(cx);
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: I) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        this.codec.encode(item, &mut this.write_buffer)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        poll_flush_buffer(&mut this.inner, cx, &mut this.write_buffer).map_err(Into::into)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx).map_err(Into::into)
+    }
+}
@@ -0,0 +1,225 @@
+//! Length-delimited frames, the `WaterBuffer` counterpart of tokio-util's `LengthDelimitedCodec`.
+//!
+//! A frame looks like
+//!
+//! ```text
+//! | length_field_offset bytes | length field | body ... |
+//! ```
+//!
+//! where the body length is the decoded length field plus `length_adjustment`.
+//! The whole frame (header and body) is consumed once it is handed out,
+//! `num_skip` controls how many header bytes are hidden from the returned frame.
+
+use std::fmt;
+use std::io;
+
+use super::{Frame, WaterDecoder, WaterEncoder};
+use crate::{Endian, WaterBuffer};
+
+/// default limit for a single frame, 8 MiB
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// Errors returned by [`LengthDelimitedCodec`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    /// the frame announced in the header is larger than the configured maximum
+    TooLarge {
+        /// announced frame length including the header
+        len: u64,
+        /// configured maximum
+        max: usize,
+    },
+    /// the length field does not describe a valid frame,
+    /// e.g. it is negative after the adjustment or shorter than `num_skip`
+    InvalidLength,
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::TooLarge { len, max } => {
+                write!(f, "frame of {} bytes exceeds the maximum of {} bytes", len, max)
+            }
+            FrameError::InvalidLength => f.write_str("invalid frame length"),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+impl From<FrameError> for io::Error {
+    fn from(error: FrameError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Configurable length-delimited frame decoder and encoder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthDelimitedCodec {
+    length_field_offset: usize,
+    length_field_len: usize,
+    endian: Endian,
+    length_adjustment: i64,
+    num_skip: Option<usize>,
+    max_frame_length: usize,
+}
+
+impl Default for LengthDelimitedCodec {
+    fn default() -> Self {
+        LengthDelimitedCodec::new()
+    }
+}
+
+impl LengthDelimitedCodec {
+    /// creating a codec for a 4 byte big endian length in front of the body
+    pub const fn new() -> LengthDelimitedCodec {
+        LengthDelimitedCodec {
+            length_field_offset: 0,
+            length_field_len: 4,
+            endian: Endian::Big,
+            length_adjustment: 0,
+            num_skip: None,
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+        }
+    }
+
+    /// number of header bytes in front of the length field
+    pub const fn length_field_offset(mut self, offset: usize) -> Self {
+        self.length_field_offset = offset;
+        self
+    }
+
+    /// width of the length field in bytes
+    ///
+    /// # Panics
+    /// when `len` is zero or larger than 8
+    pub const fn length_field_length(mut self, len: usize) -> Self {
+        assert!(len >= 1 && len <= 8, "Invalid length field width");
+        self.length_field_len = len;
+        self
+    }
+
+    /// byte order of the length field
+    pub const fn endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// added to the length field to get the number of bytes following it,
+    /// e.g. `-4` when the length counts its own `u32` field
+    pub const fn length_adjustment(mut self, adjustment: i64) -> Self {
+        self.length_adjustment = adjustment;
+        self
+    }
+
+    /// number of bytes stripped from the start of each returned frame,
+    /// defaults to the whole header (offset + length field)
+    pub const fn num_skip(mut self, num_skip: usize) -> Self {
+        self.num_skip = Some(num_skip);
+        self
+    }
+
+    /// largest accepted frame, header included
+    pub const fn max_frame_length(mut self, max: usize) -> Self {
+        self.max_frame_length = max;
+        self
+    }
+
+    /// header bytes up to the end of the length field
+    #[inline]
+    const fn head_len(&self) -> usize {
+        self.length_field_offset + self.length_field_len
+    }
+
+    /// Reads the header at the start of the readable window.
+    /// Returns the total frame length (header included) once the header is complete.
+    fn frame_len(&self, src: &WaterBuffer<u8>) -> Result<Option<usize>, FrameError> {
+        let head_len = self.head_len();
+        if src.len() < head_len {
+            return Ok(None);
+        }
+        let field = self.endian.read_uint(&src[self.length_field_offset..head_len]);
+        let body = field as i128 + self.length_adjustment as i128;
+        if body < 0 {
+            return Err(FrameError::InvalidLength);
+        }
+        let total = body + head_len as i128;
+        if total > self.max_frame_length as i128 {
+            return Err(FrameError::TooLarge {
+                len: total.min(u64::MAX as i128) as u64,
+                max: self.max_frame_length,
+            });
+        }
+        if total < self.num_skip.unwrap_or(0) as i128 {
+            return Err(FrameError::InvalidLength);
+        }
+        Ok(Some(total as usize))
+    }
+
+    /// Decodes the next frame without copying.
+    /// The returned [`Frame`] borrows the buffer and consumes the frame when dropped.
+    /// When the frame is still incomplete, capacity for the rest of it is reserved
+    /// (after the size has been checked against the maximum) and `None` is returned.
+    pub fn decode<'a>(&mut self, src: &'a mut WaterBuffer<u8>) -> Result<Option<Frame<'a>>, FrameError> {
+        let Some(total) = self.frame_len(src)? else {
+            src.reserve(self.head_len() - src.len());
+            return Ok(None);
+        };
+        if src.len() < total {
+            src.reserve(total - src.len());
+            return Ok(None);
+        }
+        Ok(Some(Frame::new(src, self.num_skip.unwrap_or(self.head_len()), total, total)))
+    }
+
+    /// Appends `body` to `dst` with a header matching this codec.
+    /// Bytes in front of the length field are written as zeros.
+    pub fn encode(&self, body: &[u8], dst: &mut WaterBuffer<u8>) -> Result<(), FrameError> {
+        let head_len = self.head_len();
+        let total = head_len + body.len();
+        if total > self.max_frame_length {
+            return Err(FrameError::TooLarge {
+                len: total as u64,
+                max: self.max_frame_length,
+            });
+        }
+        let field = body.len() as i128 - self.length_adjustment as i128;
+        let max_field = (1i128 << (8 * self.length_field_len)) - 1;
+        if field < 0 || field > max_field {
+            return Err(FrameError::InvalidLength);
+        }
+        dst.reserve(total);
+        let out = &mut dst.chunk_mut()[..head_len];
+        out[..self.length_field_offset].fill(0);
+        self.endian.write_uint(field as u64, &mut out[self.length_field_offset..]);
+        dst.advance_mut(head_len);
+        dst.extend_from_slice(body);
+        Ok(())
+    }
+}
+
+impl WaterDecoder for LengthDelimitedCodec {
+    type Item = WaterBuffer<u8>;
+    type Error = io::Error;
+
+    /// copies the frame into its own buffer, [`LengthDelimitedCodec::decode`] borrows it instead
+    fn decode(&mut self, src: &mut WaterBuffer<u8>) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(LengthDelimitedCodec::decode(self, src)?.map(|frame| WaterBuffer::from(&frame[..])))
+    }
+}
+
+impl WaterEncoder<&[u8]> for LengthDelimitedCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &[u8], dst: &mut WaterBuffer<u8>) -> Result<(), Self::Error> {
+        Ok(LengthDelimitedCodec::encode(self, item, dst)?)
+    }
+}
+
+impl WaterEncoder<WaterBuffer<u8>> for LengthDelimitedCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: WaterBuffer<u8>, dst: &mut WaterBuffer<u8>) -> Result<(), Self::Error> {
+        Ok(LengthDelimitedCodec::encode(self, &item, dst)?)
+    }
+}
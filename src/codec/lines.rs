@@ -0,0 +1,240 @@
+//! Line and delimiter framing for text protocols (SMTP, Redis inline commands, logs).
+//!
+//! [`LineDecoder`] scans the readable window for the delimiter, remembering where the
+//! previous scan stopped so bytes are never scanned twice while a line is incomplete.
+//! Lines longer than the configured maximum are reported once and then discarded up to
+//! the next delimiter, so a misbehaving peer can not make the buffer grow without bound.
+
+use std::fmt;
+use std::io;
+
+use super::{Frame, WaterDecoder, WaterEncoder};
+use crate::WaterBuffer;
+
+/// default maximum line length, 64 KiB
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 64 * 1024;
+
+/// What separates two lines
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Delimiter {
+    /// `\n`, a preceding `\r` is stripped from the line as well
+    Lf,
+    /// strictly `\r\n`
+    CrLf,
+    /// any single byte
+    Byte(u8),
+    /// any non-empty byte sequence
+    Bytes(Vec<u8>),
+}
+
+impl Delimiter {
+    /// bytes matched by the scan
+    fn pattern(&self) -> &[u8] {
+        match self {
+            Delimiter::Lf => b"\n",
+            Delimiter::CrLf => b"\r\n",
+            Delimiter::Byte(byte) => std::slice::from_ref(byte),
+            Delimiter::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+/// Errors returned by [`LineDecoder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineError {
+    /// the line is longer than the configured maximum, it will be discarded
+    TooLong {
+        /// configured maximum
+        max: usize,
+    },
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineError::TooLong { max } => write!(f, "line exceeds the maximum of {} bytes", max),
+        }
+    }
+}
+
+impl std::error::Error for LineError {}
+
+impl From<LineError> for io::Error {
+    fn from(error: LineError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Incremental line decoder over a `WaterBuffer` read buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineDecoder {
+    delimiter: Delimiter,
+    max_length: usize,
+    /// readable offset where the next scan starts
+    next_index: usize,
+    /// dropping the rest of a line that was too long
+    discarding: bool,
+}
+
+impl Default for LineDecoder {
+    fn default() -> Self {
+        LineDecoder::new(Delimiter::Lf)
+    }
+}
+
+impl LineDecoder {
+    /// creating a decoder splitting on `delimiter`
+    ///
+    /// # Panics
+    /// when `delimiter` is an empty sequence
+    pub fn new(delimiter: Delimiter) -> LineDecoder {
+        assert!(!delimiter.pattern().is_empty(), "Empty line delimiter");
+        LineDecoder {
+            delimiter,
+            max_length: DEFAULT_MAX_LINE_LENGTH,
+            next_index: 0,
+            discarding: false,
+        }
+    }
+
+    /// longest accepted line, not counting the delimiter
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// returns the delimiter used by this decoder
+    pub fn delimiter(&self) -> &Delimiter {
+        &self.delimiter
+    }
+
+    /// Scans from where the previous call stopped.
+    /// Returns the start of the delimiter, or remembers how far the scan got.
+    fn scan(&mut self, readable: &[u8]) -> Option<usize> {
+        let pattern = self.delimiter.pattern();
+        let found = match pattern {
+            [byte] => readable[self.next_index..].iter().position(|b| b == byte),
+            _ => readable[self.next_index..]
+                .windows(pattern.len())
+                .position(|window| window == pattern),
+        };
+        match found {
+            Some(pos) => Some(self.next_index + pos),
+            None => {
+                // a partial delimiter may be sitting at the end
+                self.next_index = readable.len().saturating_sub(pattern.len() - 1).max(self.next_index);
+                None
+            }
+        }
+    }
+
+    /// Drops bytes up to and including the next delimiter after a line was too long.
+    /// Returns `true` when the decoder is back in sync.
+    fn discard(&mut self, src: &mut WaterBuffer<u8>) -> bool {
+        match self.scan(&src[..]) {
+            Some(pos) => {
+                src.advance(pos + self.delimiter.pattern().len());
+                self.next_index = 0;
+                self.discarding = false;
+                true
+            }
+            None => {
+                src.advance(self.next_index);
+                self.next_index = 0;
+                false
+            }
+        }
+    }
+
+    /// Finds the next complete line, returning its length without the delimiter
+    /// and the number of bytes it consumes
+    fn next_line(&mut self, src: &mut WaterBuffer<u8>) -> Result<Option<(usize, usize)>, LineError> {
+        if self.discarding && !self.discard(src) {
+            return Ok(None);
+        }
+        let Some(pos) = self.scan(&src[..]) else {
+            // leave room for the `\r` that `Lf` strips
+            let slack = (self.delimiter == Delimiter::Lf) as usize;
+            if self.next_index > self.max_length + slack {
+                self.discarding = true;
+                return Err(LineError::TooLong { max: self.max_length });
+            }
+            return Ok(None);
+        };
+        self.next_index = 0;
+        let consumed = pos + self.delimiter.pattern().len();
+        let end = match self.delimiter {
+            Delimiter::Lf if pos > 0 && src[pos - 1] == b'\r' => pos - 1,
+            _ => pos,
+        };
+        if end > self.max_length {
+            src.advance(consumed);
+            return Err(LineError::TooLong { max: self.max_length });
+        }
+        Ok(Some((end, consumed)))
+    }
+
+    /// Decodes the next line without copying.
+    /// The returned [`Frame`] excludes the delimiter and consumes the line and
+    /// the delimiter when dropped.
+    pub fn decode<'a>(&mut self, src: &'a mut WaterBuffer<u8>) -> Result<Option<Frame<'a>>, LineError> {
+        Ok(self
+            .next_line(src)?
+            .map(|(end, consumed)| Frame::new(src, 0, end, consumed)))
+    }
+
+    /// Like [`LineDecoder::decode`], but once the stream has ended the remaining
+    /// bytes are returned as a final line without a delimiter
+    pub fn decode_eof<'a>(&mut self, src: &'a mut WaterBuffer<u8>) -> Result<Option<Frame<'a>>, LineError> {
+        if let Some((end, consumed)) = self.next_line(src)? {
+            return Ok(Some(Frame::new(src, 0, end, consumed)));
+        }
+        if self.discarding {
+            src.clear();
+            self.discarding = false;
+        }
+        self.next_index = 0;
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let len = src.len();
+        if len > self.max_length {
+            src.clear();
+            return Err(LineError::TooLong { max: self.max_length });
+        }
+        Ok(Some(Frame::new(src, 0, len, len)))
+    }
+}
+
+impl WaterDecoder for LineDecoder {
+    type Item = WaterBuffer<u8>;
+    type Error = io::Error;
+
+    /// copies the line into its own buffer, [`LineDecoder::decode`] borrows it instead
+    fn decode(&mut self, src: &mut WaterBuffer<u8>) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(LineDecoder::decode(self, src)?.map(|line| WaterBuffer::from(&line[..])))
+    }
+
+    fn decode_eof(&mut self, src: &mut WaterBuffer<u8>) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(LineDecoder::decode_eof(self, src)?.map(|line| WaterBuffer::from(&line[..])))
+    }
+}
+
+impl WaterEncoder<&[u8]> for LineDecoder {
+    type Error = io::Error;
+
+    /// writes the line followed by the delimiter
+    fn encode(&mut self, item: &[u8], dst: &mut WaterBuffer<u8>) -> Result<(), Self::Error> {
+        dst.extend_from_slice(item);
+        dst.extend_from_slice(self.delimiter.pattern());
+        Ok(())
+    }
+}
+
+impl WaterEncoder<&str> for LineDecoder {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &str, dst: &mut WaterBuffer<u8>) -> Result<(), Self::Error> {
+        WaterEncoder::encode(self, item.as_bytes(), dst)
+    }
+}
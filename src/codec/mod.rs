@@ -0,0 +1,105 @@
+//! Frame codecs operating directly on a `WaterBuffer` read buffer.
+//!
+//! Decoders look at the readable window (`start_pos .. start_pos + filled_data_length`)
+//! and only `advance` once a whole frame has been handed out, so a partial frame is
+//! simply left in place until the next read completes it.
+//!
+//! The inherent `decode` methods return a [`Frame`] borrowed from the read buffer, this is
+//! the zero-copy path. The [`WaterDecoder`] implementations hand out owned items and
+//! therefore copy every frame into its own buffer.
+
+use std::fmt;
+use std::io;
+use std::ops::Deref;
+
+use crate::WaterBuffer;
+
+#[cfg(feature = "tokio")]
+mod framed;
+mod length_delimited;
+mod lines;
+
+#[cfg(feature = "tokio")]
+pub use framed::*;
+pub use length_delimited::*;
+pub use lines::*;
+
+/// Decodes owned frames out of a `WaterBuffer` read buffer, the `WaterBuffer`
+/// counterpart of tokio-util's `Decoder`
+pub trait WaterDecoder {
+    /// the decoded frame
+    type Item;
+    /// the error type, io errors from the underlying reader are converted into it
+    type Error: From<io::Error>;
+
+    /// Decodes the next frame, returning `None` when more bytes are needed.
+    /// Consumed bytes are removed with `advance`.
+    fn decode(&mut self, src: &mut WaterBuffer<u8>) -> Result<Option<Self::Item>, Self::Error>;
+
+    /// Called once the reader has reached EOF.
+    /// By default bytes that do not form a complete frame are an error.
+    fn decode_eof(&mut self, src: &mut WaterBuffer<u8>) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None if src.is_empty() => Ok(None),
+            None => Err(io::Error::other("bytes remaining on stream").into()),
+        }
+    }
+}
+
+/// Encodes frames into a `WaterBuffer` write buffer, the `WaterBuffer`
+/// counterpart of tokio-util's `Encoder`
+pub trait WaterEncoder<Item> {
+    /// the error type, io errors from the underlying writer are converted into it
+    type Error: From<io::Error>;
+
+    /// Appends `item` to `dst`
+    fn encode(&mut self, item: Item, dst: &mut WaterBuffer<u8>) -> Result<(), Self::Error>;
+}
+
+/// A decoded frame borrowed from the read buffer.
+/// Dereferences to the frame bytes and consumes the whole frame,
+/// including headers and delimiters, from the buffer when dropped.
+pub struct Frame<'a> {
+    buffer: &'a mut WaterBuffer<u8>,
+    start: usize,
+    end: usize,
+    consumed: usize,
+}
+
+impl<'a> Frame<'a> {
+    /// `start..end` is the visible frame, `consumed` the bytes advanced on drop
+    pub(crate) fn new(buffer: &'a mut WaterBuffer<u8>, start: usize, end: usize, consumed: usize) -> Frame<'a> {
+        Frame {
+            buffer,
+            start,
+            end,
+            consumed,
+        }
+    }
+
+    /// every byte this frame consumes, including headers and delimiters
+    pub fn raw(&self) -> &[u8] {
+        &self.buffer[..self.consumed]
+    }
+}
+
+impl Deref for Frame<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer[self.start..self.end]
+    }
+}
+
+impl fmt::Debug for Frame<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Frame").field(&crate::Escaped::new(self)).finish()
+    }
+}
+
+impl Drop for Frame<'_> {
+    fn drop(&mut self) {
+        self.buffer.advance(self.consumed);
+    }
+}
@@ -0,0 +1,250 @@
+//! Registered (fixed) buffers for `read_fixed` / `write_fixed`.
+//!
+//! [`FixedWaterPool`] allocates a set of equally sized `WaterBuffer`s and hands them to
+//! tokio-uring's `FixedBufPool`, which registers them with the ring once. Checking a buffer
+//! out returns a [`FixedWaterBuffer`], a readable window over the registered memory. It is not
+//! a `WaterBuffer`: tokio-uring keeps the pooled buffers and only lends out their memory, so it
+//! offers a small subset of the cursor methods (`advance`, `chunk_mut` / `advance_mut`,
+//! `extend_from_slice`, `compact`, ...) and [`FixedWaterBuffer::to_water_buffer`] copies the
+//! readable window when a `WaterBuffer` is needed. Dropping the handle checks the buffer back in.
+//!
+//! Registered memory can not grow, writes that do not fit after compacting the
+//! readable window to the front panic, reads into a full buffer fail with `OutOfMemory`.
+
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+
+use tokio_uring::buf::fixed::{FixedBuf, FixedBufPool};
+use tokio_uring::buf::{BoundedBuf, IoBuf, IoBufMut};
+use tokio_uring::net::TcpStream;
+use tokio_uring::BufResult;
+
+use crate::WaterBuffer;
+
+/// A set of `WaterBuffer`s registered with the io_uring instance
+pub struct FixedWaterPool {
+    pool: FixedBufPool<WaterBuffer<u8>>,
+    buffer_size: usize,
+}
+
+impl FixedWaterPool {
+    /// creating a pool of `count` buffers of `buffer_size` bytes each,
+    /// [`FixedWaterPool::register`] has to be called inside the runtime before use
+    ///
+    /// # Panics
+    /// when `buffer_size` is zero
+    pub fn new(count: usize, buffer_size: usize) -> FixedWaterPool {
+        assert!(buffer_size > 0, "Empty fixed buffer");
+        let buffers = (0..count).map(|_| WaterBuffer::with_capacity(buffer_size));
+        FixedWaterPool {
+            pool: FixedBufPool::new(buffers),
+            buffer_size,
+        }
+    }
+
+    /// Registers the buffers with the ring of the current tokio-uring runtime
+    pub fn register(&self) -> io::Result<()> {
+        self.pool.register()
+    }
+
+    /// Unregisters the buffers, handles still checked out can no longer be used for IO
+    pub fn unregister(&self) -> io::Result<()> {
+        self.pool.unregister()
+    }
+
+    /// returning the capacity of every buffer in the pool
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Checks out a free buffer, `None` when all of them are in use
+    pub fn try_check_out(&self) -> Option<FixedWaterBuffer> {
+        self.pool.try_next(self.buffer_size).map(FixedWaterBuffer::new)
+    }
+
+    /// Checks out a free buffer, waiting for one to be returned when all of them are in use
+    pub async fn check_out(&self) -> FixedWaterBuffer {
+        FixedWaterBuffer::new(self.pool.next(self.buffer_size).await)
+    }
+}
+
+/// A buffer checked out of a [`FixedWaterPool`], returned to the pool on drop.
+/// Tracks its own readable window over the registered memory, which never grows.
+pub struct FixedWaterBuffer {
+    buf: FixedBuf,
+    /// offset of the first readable byte
+    start: usize,
+    /// offset right after the last readable byte
+    end: usize,
+}
+
+impl FixedWaterBuffer {
+    /// a freshly checked out buffer is empty, whatever a previous user left in it
+    fn new(buf: FixedBuf) -> FixedWaterBuffer {
+        FixedWaterBuffer { buf, start: 0, end: 0 }
+    }
+
+    /// index of the registered buffer
+    pub fn buf_index(&self) -> u16 {
+        self.buf.buf_index()
+    }
+
+    /// number of readable bytes
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// returns `true` when there is nothing to read
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// size of the registered buffer
+    #[inline(always)]
+    pub fn cap(&self) -> usize {
+        IoBuf::bytes_total(&self.buf)
+    }
+
+    /// number of bytes that can be written after the readable window without compacting
+    #[inline(always)]
+    pub fn mut_len(&self) -> usize {
+        self.cap() - self.end
+    }
+
+    /// Consumes `n` readable bytes
+    ///
+    /// # Panics
+    /// when `n` is larger than [`FixedWaterBuffer::len`]
+    pub fn advance(&mut self, n: usize) {
+        if n > self.len() {
+            panic!("Insufficient space to advance");
+        }
+        self.start += n;
+        if self.start == self.end {
+            self.clear();
+        }
+    }
+
+    /// Drops all readable bytes
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.end = 0;
+    }
+
+    /// Keeps the first `len` readable bytes
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.end = self.start + len;
+        }
+    }
+
+    /// Moves the readable window to the front of the registered buffer
+    pub fn compact(&mut self) {
+        if self.start == 0 {
+            return;
+        }
+        let len = self.len();
+        unsafe {
+            let base = IoBufMut::stable_mut_ptr(&mut self.buf);
+            ptr::copy(base.add(self.start), base, len);
+        }
+        self.start = 0;
+        self.end = len;
+    }
+
+    /// returning the writable part after the readable window, commit written bytes with `advance_mut`
+    pub fn chunk_mut(&mut self) -> &mut [u8] {
+        let len = self.mut_len();
+        unsafe { std::slice::from_raw_parts_mut(IoBufMut::stable_mut_ptr(&mut self.buf).add(self.end), len) }
+    }
+
+    /// Commits `n` bytes written into [`FixedWaterBuffer::chunk_mut`]
+    ///
+    /// # Panics
+    /// when `n` is larger than [`FixedWaterBuffer::mut_len`]
+    pub fn advance_mut(&mut self, n: usize) {
+        assert!(n <= self.mut_len(), "Fixed buffer capacity exceeded");
+        self.end += n;
+        unsafe { IoBufMut::set_init(&mut self.buf, self.end) };
+    }
+
+    /// Appends `slice`, compacting first when it does not fit after the readable window
+    ///
+    /// # Panics
+    /// when the readable window plus `slice` is larger than the registered buffer
+    pub fn extend_from_slice(&mut self, slice: &[u8]) {
+        if slice.len() > self.mut_len() {
+            self.compact();
+        }
+        assert!(slice.len() <= self.mut_len(), "Fixed buffer capacity exceeded");
+        self.chunk_mut()[..slice.len()].copy_from_slice(slice);
+        self.advance_mut(slice.len());
+    }
+
+    /// Copies the readable window into an owned `WaterBuffer`, leaving this buffer untouched
+    pub fn to_water_buffer(&self) -> WaterBuffer<u8> {
+        WaterBuffer::from(&self[..])
+    }
+}
+
+impl Deref for FixedWaterBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.buf[self.start..self.end]
+    }
+}
+
+impl DerefMut for FixedWaterBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buf[self.start..self.end]
+    }
+}
+
+impl std::fmt::Debug for FixedWaterBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FixedWaterBuffer")
+            .field("buf_index", &self.buf_index())
+            .field("len", &self.len())
+            .field("cap", &self.cap())
+            .finish()
+    }
+}
+
+/// Reads once with `read_fixed` into the spare capacity of a registered buffer,
+/// compacting it first when there is no room after the readable window.
+/// Returns the number of bytes read, `0` meaning EOF.
+pub async fn read_more_fixed(stream: &TcpStream, mut buffer: FixedWaterBuffer) -> BufResult<usize, FixedWaterBuffer> {
+    if buffer.mut_len() == 0 {
+        buffer.compact();
+    }
+    if buffer.mut_len() == 0 {
+        return (Err(io::Error::new(io::ErrorKind::OutOfMemory, "fixed buffer is full")), buffer);
+    }
+    let FixedWaterBuffer { buf, start, end } = buffer;
+    let (result, slice) = stream.read_fixed(buf.slice(end..)).await;
+    let mut buffer = FixedWaterBuffer { buf: slice.into_inner(), start, end };
+    if let Ok(n) = result {
+        buffer.end += n;
+    }
+    (result, buffer)
+}
+
+/// Writes the whole readable window of a registered buffer with `write_fixed`,
+/// advancing past every partial write
+pub async fn write_all_fixed(stream: &TcpStream, mut buffer: FixedWaterBuffer) -> BufResult<(), FixedWaterBuffer> {
+    while !buffer.is_empty() {
+        let FixedWaterBuffer { buf, start, end } = buffer;
+        let (result, slice) = stream.write_fixed(buf.slice(start..end)).await;
+        buffer = FixedWaterBuffer { buf: slice.into_inner(), start, end };
+        match result {
+            Ok(0) => return (Err(io::ErrorKind::WriteZero.into()), buffer),
+            Ok(n) => buffer.advance(n),
+            Err(error) => return (Err(error), buffer),
+        }
+    }
+    (Ok(()), buffer)
+}
@@ -1,40 +1,158 @@
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+
 use tokio_uring::buf::IoBuf;
+use tokio_uring::net::TcpStream;
+use tokio_uring::BufResult;
+
+use crate::WaterBuffer;
 
+mod fixed;
+mod ring;
+pub use fixed::*;
+pub use ring::*;
 
-/// unsafe BytesSlice Wrapper for Sending bytes safely to tokio uring api
-pub struct BytesSliceWrapper {
-    bytes:*const u8,
-    len:usize,
+/// Bytes handed to tokio-uring for writing, every variant keeps its memory alive
+/// until the operation has completed and the wrapper has been returned
+#[derive(Debug, Clone)]
+pub enum BytesSliceWrapper {
+    /// bytes living for the whole program
+    Static(&'static [u8]),
+    /// reference counted bytes, cloning the handle does not copy them
+    Shared {
+        /// the shared allocation
+        bytes: Arc<[u8]>,
+        /// part of `bytes` to write
+        range: Range<usize>,
+    },
+    /// a region of the readable window of a buffer, owned until the operation completes
+    Buffer {
+        /// the buffer, hand it back with [`BytesSliceWrapper::into_buffer`]
+        buffer: WaterBuffer<u8>,
+        /// part of the readable window to write
+        range: Range<usize>,
+    },
 }
 
 impl BytesSliceWrapper {
+    /// creating a wrapper over the whole of `bytes`
+    pub fn shared(bytes: Arc<[u8]>) -> BytesSliceWrapper {
+        let range = 0..bytes.len();
+        BytesSliceWrapper::Shared { bytes, range }
+    }
+
+    /// creating a wrapper over `range` of `bytes`
+    ///
+    /// # Panics
+    /// when `range` is out of bounds
+    pub fn shared_range(bytes: Arc<[u8]>, range: Range<usize>) -> BytesSliceWrapper {
+        assert!(range.start <= range.end && range.end <= bytes.len(), "Range out of bounds");
+        BytesSliceWrapper::Shared { bytes, range }
+    }
+
+    /// creating a wrapper over `range` of the readable window of `buffer`
+    ///
+    /// # Panics
+    /// when `range` is out of bounds
+    pub fn buffer_range(buffer: WaterBuffer<u8>, range: Range<usize>) -> BytesSliceWrapper {
+        assert!(range.start <= range.end && range.end <= buffer.len(), "Range out of bounds");
+        BytesSliceWrapper::Buffer { buffer, range }
+    }
 
-    /// creating new BytesSliceWrapper from ref [[u8]]
-    pub fn new(bytes:&[u8])->BytesSliceWrapper{
-        let d = unsafe {bytes.as_ptr()};
-        BytesSliceWrapper {
-            bytes:d,
-            len:bytes.len()
+    /// returning the buffer of a `Buffer` wrapper, `None` for the other variants
+    pub fn into_buffer(self) -> Option<WaterBuffer<u8>> {
+        match self {
+            BytesSliceWrapper::Buffer { buffer, .. } => Some(buffer),
+            _ => None,
         }
     }
+
+    /// returning the bytes to write
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            BytesSliceWrapper::Static(bytes) => bytes,
+            BytesSliceWrapper::Shared { bytes, range } => &bytes[range.clone()],
+            BytesSliceWrapper::Buffer { buffer, range } => &buffer[range.clone()],
+        }
+    }
+}
+
+impl From<&'static [u8]> for BytesSliceWrapper {
+    fn from(bytes: &'static [u8]) -> Self {
+        BytesSliceWrapper::Static(bytes)
+    }
+}
+
+impl From<&'static str> for BytesSliceWrapper {
+    fn from(bytes: &'static str) -> Self {
+        BytesSliceWrapper::Static(bytes.as_bytes())
+    }
+}
+
+impl From<Arc<[u8]>> for BytesSliceWrapper {
+    fn from(bytes: Arc<[u8]>) -> Self {
+        BytesSliceWrapper::shared(bytes)
+    }
 }
 
-impl Into<BytesSliceWrapper> for &'_ [u8]{
-    fn into(self) -> BytesSliceWrapper {
-        BytesSliceWrapper::new(self)
+impl From<WaterBuffer<u8>> for BytesSliceWrapper {
+    fn from(buffer: WaterBuffer<u8>) -> Self {
+        let range = 0..buffer.len();
+        BytesSliceWrapper::Buffer { buffer, range }
     }
 }
 
 unsafe impl IoBuf for BytesSliceWrapper {
     fn stable_ptr(&self) -> *const u8 {
-        self.bytes
+        // every variant points into a heap allocation or static memory, moving the wrapper keeps it in place
+        self.as_slice().as_ptr()
     }
 
     fn bytes_init(&self) -> usize {
-        self.len
+        self.as_slice().len()
     }
 
     fn bytes_total(&self) -> usize {
-        self.len
+        self.as_slice().len()
     }
-}
\ No newline at end of file
+}
+
+/// Reads once from `stream` into the spare capacity of `buffer`.
+/// At least `min` bytes of spare capacity are reserved before the read is submitted,
+/// the buffer is handed back together with the number of bytes read, `0` meaning EOF.
+pub async fn read_more(stream: &TcpStream, buffer: WaterBuffer<u8>, min: usize) -> BufResult<usize, WaterBuffer<u8>> {
+    let (result, slice) = stream.read(buffer.into_read_slice(min.max(1))).await;
+    (result, slice.into_inner())
+}
+
+/// Reads from `stream` until at least `len` bytes are readable in `buffer`.
+/// Fails with `UnexpectedEof` when the stream ends first, the bytes read so far stay in the buffer.
+pub async fn read_exact(stream: &TcpStream, mut buffer: WaterBuffer<u8>, len: usize) -> BufResult<(), WaterBuffer<u8>> {
+    while buffer.len() < len {
+        let missing = len - buffer.len();
+        let (result, returned) = read_more(stream, buffer, missing).await;
+        buffer = returned;
+        match result {
+            Ok(0) => return (Err(io::ErrorKind::UnexpectedEof.into()), buffer),
+            Ok(_) => {}
+            Err(error) => return (Err(error), buffer),
+        }
+    }
+    (Ok(()), buffer)
+}
+
+/// Writes the whole readable window of `buffer` to `stream`, advancing past every partial write.
+/// The buffer comes back empty on success, on error it still holds the unwritten bytes.
+pub async fn write_all(stream: &TcpStream, mut buffer: WaterBuffer<u8>) -> BufResult<(), WaterBuffer<u8>> {
+    while !buffer.is_empty() {
+        let (result, slice) = stream.write(buffer.into_write_slice()).submit().await;
+        buffer = slice.into_inner();
+        match result {
+            Ok(0) => return (Err(io::ErrorKind::WriteZero.into()), buffer),
+            Ok(n) => buffer.advance(n),
+            Err(error) => return (Err(error), buffer),
+        }
+    }
+    (Ok(()), buffer)
+}
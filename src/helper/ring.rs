@@ -0,0 +1,355 @@
+//! Provided buffers (buffer selection) for receives.
+//!
+//! [`WaterBufRing`] splits one `WaterBuffer` allocation into equally sized chunks and
+//! provides them to the kernel as a buffer group. A receive submitted with
+//! [`WaterBufRing::recv`] does not carry a buffer, the kernel picks a free chunk when data
+//! arrives, so idle connections do not pin any memory. The chunk comes back as a
+//! [`WaterRingBuf`] view which hands the chunk back to the ring when dropped.
+//!
+//! tokio-uring 0.5 neither exposes `register_buf_ring` nor multishot completions, so the
+//! group is filled with `IORING_OP_PROVIDE_BUFFERS` and every receive is a single shot.
+//! Submitting panics outside of the tokio-uring driver and a chunk may be dropped anywhere,
+//! so dropped chunks are only queued and provided again by the next
+//! [`WaterBufRing::recv`], right before its receive is submitted.
+//! [`WaterBufRing::unregister`] discards the queue and waits for re-provides in flight
+//! before taking the group back.
+//!
+//! The memory is only freed once the kernel can no longer write into it. A ring dropped
+//! while still provided can not take its chunks back, its memory is leaked, so call
+//! [`WaterBufRing::unregister`] first.
+
+use std::cell::{Cell, RefCell};
+use std::future::poll_fn;
+use std::io;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::rc::Rc;
+use std::task::{Poll, Waker};
+
+use io_uring::{cqueue, opcode, squeue, types};
+use tokio_uring::{OneshotOutputTransform, UnsubmittedOneshot};
+
+use crate::WaterBuffer;
+
+/// Whether the kernel may hold chunks of the ring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RingState {
+    /// nothing is provided, the memory is ours
+    Idle,
+    /// the chunks are provided, dropped chunks are provided again
+    Provided,
+    /// [`WaterBufRing::unregister`] is taking the chunks back, dropped chunks are not queued
+    Removing,
+}
+
+/// Memory and bookkeeping shared by the ring and its chunks,
+/// kept alive by every operation in flight
+struct RingInner {
+    memory: ManuallyDrop<WaterBuffer<u8>>,
+    group_id: u16,
+    count: u16,
+    buffer_size: usize,
+    state: Cell<RingState>,
+    /// chunks handed out as `WaterRingBuf`
+    in_use: Cell<usize>,
+    /// dropped chunks waiting for the next `recv` to provide them again
+    pending: RefCell<Vec<u16>>,
+    /// re-provide submissions whose completion has not arrived yet
+    reproviding: Cell<usize>,
+    /// `unregister` waiting for `reproviding` to reach zero
+    waiter: Cell<Option<Waker>>,
+}
+
+impl RingInner {
+    /// address of chunk `bid`
+    fn chunk_ptr(&self, bid: u16) -> *mut u8 {
+        unsafe { self.memory.pointer.add(bid as usize * self.buffer_size) }
+    }
+}
+
+impl Drop for RingInner {
+    fn drop(&mut self) {
+        // the kernel may still write into provided chunks and a removal can not be submitted
+        // from an arbitrary drop, leaking them is the only safe option
+        if self.state.get() == RingState::Idle {
+            unsafe { ManuallyDrop::drop(&mut self.memory) };
+        }
+    }
+}
+
+/// Counts a re-provide submission as outstanding until its completion arrives
+struct Reprovide(Rc<RingInner>);
+
+impl Reprovide {
+    fn new(ring: &Rc<RingInner>) -> Reprovide {
+        ring.reproviding.set(ring.reproviding.get() + 1);
+        Reprovide(Rc::clone(ring))
+    }
+}
+
+impl Drop for Reprovide {
+    fn drop(&mut self) {
+        let ring = &self.0;
+        ring.reproviding.set(ring.reproviding.get() - 1);
+        if ring.reproviding.get() == 0
+            && let Some(waker) = ring.waiter.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A group of equally sized buffers the kernel picks from on receive
+pub struct WaterBufRing {
+    inner: Rc<RingInner>,
+}
+
+impl WaterBufRing {
+    /// creating `count` chunks of `buffer_size` bytes for buffer group `group_id`,
+    /// [`WaterBufRing::provide`] has to be called inside the runtime before use
+    ///
+    /// # Panics
+    /// when `count` or `buffer_size` is zero or a chunk is larger than `i32::MAX`
+    pub fn new(group_id: u16, count: u16, buffer_size: usize) -> WaterBufRing {
+        assert!(count > 0 && buffer_size > 0, "Empty buffer ring");
+        assert!(buffer_size <= i32::MAX as usize, "Ring buffer size too large");
+        let memory = WaterBuffer::with_capacity(count as usize * buffer_size);
+        WaterBufRing {
+            inner: Rc::new(RingInner {
+                memory: ManuallyDrop::new(memory),
+                group_id,
+                count,
+                buffer_size,
+                state: Cell::new(RingState::Idle),
+                in_use: Cell::new(0),
+                pending: RefCell::new(Vec::new()),
+                reproviding: Cell::new(0),
+                waiter: Cell::new(None),
+            }),
+        }
+    }
+
+    /// returning the buffer group id
+    pub fn group_id(&self) -> u16 {
+        self.inner.group_id
+    }
+
+    /// returning the number of chunks
+    pub fn count(&self) -> u16 {
+        self.inner.count
+    }
+
+    /// returning the size of every chunk
+    pub fn buffer_size(&self) -> usize {
+        self.inner.buffer_size
+    }
+
+    /// returning the number of chunks currently handed out
+    pub fn in_use(&self) -> usize {
+        self.inner.in_use.get()
+    }
+
+    /// Provides every chunk to the kernel.
+    /// Fails when the chunks are already provided or some of them are still handed out.
+    pub async fn provide(&self) -> io::Result<()> {
+        let inner = &self.inner;
+        if inner.state.get() != RingState::Idle {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "buffer ring is already provided"));
+        }
+        if inner.in_use.get() != 0 {
+            return Err(io::Error::new(io::ErrorKind::ResourceBusy, "buffer ring chunks are still in use"));
+        }
+        let sqe = opcode::ProvideBuffers::new(
+            inner.chunk_ptr(0),
+            inner.buffer_size as i32,
+            inner.count,
+            inner.group_id,
+            0,
+        )
+        .build();
+        inner.state.set(RingState::Provided);
+        let result = UnsubmittedOneshot::new(Rc::clone(inner), ResultTransform(PhantomData), sqe).submit().await;
+        if result.is_err() {
+            inner.state.set(RingState::Idle);
+        }
+        result
+    }
+
+    /// Takes the chunks back from the kernel, queued and later dropped chunks are not provided again.
+    /// Waits for chunks that are still being re-provided before removing the group,
+    /// afterwards the memory is freed when the ring is dropped.
+    pub async fn unregister(&self) -> io::Result<()> {
+        let inner = &self.inner;
+        if inner.state.get() == RingState::Idle {
+            return Ok(());
+        }
+        inner.state.set(RingState::Removing);
+        inner.pending.borrow_mut().clear();
+        poll_fn(|cx| {
+            if inner.reproviding.get() == 0 {
+                return Poll::Ready(());
+            }
+            inner.waiter.set(Some(cx.waker().clone()));
+            Poll::Pending
+        })
+        .await;
+        let sqe = opcode::RemoveBuffers::new(inner.count, inner.group_id).build();
+        let result = UnsubmittedOneshot::new(Rc::clone(inner), ResultTransform(PhantomData), sqe).submit().await;
+        inner.state.set(if result.is_ok() { RingState::Idle } else { RingState::Provided });
+        result
+    }
+
+    /// Receives once from `socket` into a chunk picked by the kernel,
+    /// chunks dropped since the last call are provided again first.
+    /// Returns `None` at EOF, fails with `ENOBUFS` when every chunk is in use.
+    pub async fn recv(&self, socket: &impl AsRawFd) -> io::Result<Option<WaterRingBuf>> {
+        let inner = &self.inner;
+        self.reprovide_pending();
+        let sqe = opcode::Recv::new(types::Fd(socket.as_raw_fd()), ptr::null_mut(), inner.buffer_size as u32)
+            .buf_group(inner.group_id)
+            .build()
+            .flags(squeue::Flags::BUFFER_SELECT);
+        UnsubmittedOneshot::new(Rc::clone(inner), RecvTransform, sqe).submit().await
+    }
+
+    /// Submits a provide for every queued chunk, only called inside the driver
+    fn reprovide_pending(&self) {
+        let inner = &self.inner;
+        if inner.state.get() != RingState::Provided {
+            return;
+        }
+        for bid in inner.pending.take() {
+            let sqe = opcode::ProvideBuffers::new(
+                inner.chunk_ptr(bid),
+                inner.buffer_size as i32,
+                1,
+                inner.group_id,
+                bid,
+            )
+            .build();
+            // the completion is not awaited, the operation keeps the memory alive and
+            // counts as outstanding for `unregister` until it lands
+            drop(UnsubmittedOneshot::new(Reprovide::new(inner), ResultTransform(PhantomData), sqe).submit());
+        }
+    }
+}
+
+/// Turns a plain completion into `io::Result<()>`, `D` is kept alive until the completion arrives
+struct ResultTransform<D>(PhantomData<D>);
+
+impl<D> OneshotOutputTransform for ResultTransform<D> {
+    type Output = io::Result<()>;
+    type StoredData = D;
+
+    fn transform_oneshot_output(self, _: Self::StoredData, cqe: cqueue::Entry) -> Self::Output {
+        match cqe.result() {
+            res if res < 0 => Err(io::Error::from_raw_os_error(-res)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Turns a buffer select receive completion into the chunk the kernel filled
+struct RecvTransform;
+
+impl OneshotOutputTransform for RecvTransform {
+    type Output = io::Result<Option<WaterRingBuf>>;
+    type StoredData = Rc<RingInner>;
+
+    fn transform_oneshot_output(self, ring: Self::StoredData, cqe: cqueue::Entry) -> Self::Output {
+        let res = cqe.result();
+        // a chunk picked by the kernel is ours even when the receive failed or hit EOF
+        let chunk = cqueue::buffer_select(cqe.flags()).map(|bid| {
+            ring.in_use.set(ring.in_use.get() + 1);
+            WaterRingBuf { ring, bid, start: 0, end: res.max(0) as usize }
+        });
+        if res < 0 {
+            return Err(io::Error::from_raw_os_error(-res));
+        }
+        match chunk {
+            Some(chunk) if !chunk.is_empty() => Ok(Some(chunk)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// A chunk of a [`WaterBufRing`] filled by a receive, queued to be provided again on drop
+pub struct WaterRingBuf {
+    ring: Rc<RingInner>,
+    bid: u16,
+    /// offset of the first readable byte
+    start: usize,
+    /// offset right after the last readable byte
+    end: usize,
+}
+
+impl WaterRingBuf {
+    /// id of the chunk inside its buffer group
+    pub fn buf_id(&self) -> u16 {
+        self.bid
+    }
+
+    /// number of readable bytes
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// returns `true` when there is nothing to read
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Consumes `n` readable bytes
+    ///
+    /// # Panics
+    /// when `n` is larger than [`WaterRingBuf::len`]
+    pub fn advance(&mut self, n: usize) {
+        if n > self.len() {
+            panic!("Insufficient space to advance");
+        }
+        self.start += n;
+    }
+
+    /// Copies the readable bytes into an owned `WaterBuffer`, so the chunk can be returned early
+    pub fn to_water_buffer(&self) -> WaterBuffer<u8> {
+        WaterBuffer::from(&self[..])
+    }
+}
+
+impl Deref for WaterRingBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::slice::from_raw_parts(self.ring.chunk_ptr(self.bid).add(self.start), self.len()) }
+    }
+}
+
+impl DerefMut for WaterRingBuf {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { std::slice::from_raw_parts_mut(self.ring.chunk_ptr(self.bid).add(self.start), self.len()) }
+    }
+}
+
+impl std::fmt::Debug for WaterRingBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WaterRingBuf")
+            .field("buf_id", &self.bid)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Drop for WaterRingBuf {
+    /// queues the chunk for the next [`WaterBufRing::recv`], nothing is submitted here
+    fn drop(&mut self) {
+        let ring = &self.ring;
+        ring.in_use.set(ring.in_use.get() - 1);
+        if ring.state.get() == RingState::Provided {
+            ring.pending.borrow_mut().push(self.bid);
+        }
+    }
+}
@@ -0,0 +1,425 @@
+//! Chunked transfer coding.
+//!
+//! [`ChunkedDecoder`] consumes a chunked body from a `WaterBuffer` as it arrives. Chunk data
+//! is handed out without copying as soon as any of it is readable, so a large chunk does not
+//! have to be buffered completely. Chunk extensions are validated and skipped, the trailer
+//! section is parsed with a [`HeadParser`] and handed out once complete.
+//!
+//! [`ChunkedEncoder`] writes the framing directly into a `WaterBuffer`. With
+//! [`ChunkedEncoder::begin_chunk`] the chunk body is written in place after a zero-padded
+//! size that [`ChunkedEncoder::finish_chunk`] back-patches, the same way length prefixes are.
+
+use std::fmt;
+use std::io;
+
+use super::{is_field_text, Fields, HeadError, HeadParser, Status};
+use crate::buffer::search::find_byte;
+use crate::codec::{Frame, WaterEncoder};
+use crate::WaterBuffer;
+
+/// default maximum length of a chunk size line including extensions, 4 KiB
+pub const DEFAULT_MAX_CHUNK_LINE: usize = 4 * 1024;
+
+/// default maximum size of the trailer section, 16 KiB
+pub const DEFAULT_MAX_TRAILER_SIZE: usize = 16 * 1024;
+
+/// default number of hex digits reserved by [`ChunkedEncoder::begin_chunk`]
+pub const DEFAULT_SIZE_WIDTH: usize = 8;
+
+/// Errors returned by [`ChunkedDecoder`] and [`ChunkedEncoder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkedError {
+    /// the chunk size is missing or not hexadecimal
+    InvalidSize,
+    /// the chunk size does not fit into 64 bits
+    SizeOverflow,
+    /// bytes after the chunk size are not a chunk extension
+    InvalidExtension,
+    /// the chunk size line is longer than the configured maximum
+    LineTooLong {
+        /// configured maximum
+        max: usize,
+    },
+    /// chunk data is not followed by a line break
+    MissingLineBreak,
+    /// the trailer section is invalid
+    Trailers(HeadError),
+    /// the chunk is larger than the reserved size digits can express
+    ChunkTooLarge {
+        /// bytes written after the size
+        len: usize,
+        /// largest size the reserved digits can hold
+        max: u64,
+    },
+}
+
+impl fmt::Display for ChunkedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkedError::InvalidSize => f.write_str("invalid chunk size"),
+            ChunkedError::SizeOverflow => f.write_str("chunk size overflows 64 bits"),
+            ChunkedError::InvalidExtension => f.write_str("invalid chunk extension"),
+            ChunkedError::LineTooLong { max } => write!(f, "chunk size line exceeds the maximum of {} bytes", max),
+            ChunkedError::MissingLineBreak => f.write_str("chunk data is not followed by a line break"),
+            ChunkedError::Trailers(error) => write!(f, "invalid trailers: {}", error),
+            ChunkedError::ChunkTooLarge { len, max } => {
+                write!(f, "chunk of {} bytes does not fit into the reserved size (max {})", len, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChunkedError {}
+
+impl From<ChunkedError> for io::Error {
+    fn from(error: ChunkedError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Something decoded from a chunked body
+#[derive(Debug)]
+pub enum Decoded<'a> {
+    /// chunk data, possibly only part of a chunk, consumed from the buffer when dropped
+    Data(Frame<'a>),
+    /// the trailer section ending the body, consumed from the buffer when dropped
+    Trailers(Fields<'a>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// expecting a chunk size line
+    Size,
+    /// inside chunk data with this many bytes left
+    Data(u64),
+    /// expecting the line break after chunk data
+    DataEnd,
+    /// after the last chunk, expecting the trailer section
+    Trailers,
+    /// the trailer section has been handed out
+    Done,
+}
+
+/// Stateful decoder for a chunked body
+#[derive(Debug, Clone)]
+pub struct ChunkedDecoder {
+    state: State,
+    max_line: usize,
+    /// readable offset where the scan for the end of the size line continues
+    scanned: usize,
+    trailers: HeadParser,
+}
+
+impl Default for ChunkedDecoder {
+    fn default() -> Self {
+        ChunkedDecoder::new()
+    }
+}
+
+impl ChunkedDecoder {
+    /// creating a decoder with [`DEFAULT_MAX_CHUNK_LINE`] and [`DEFAULT_MAX_TRAILER_SIZE`]
+    pub const fn new() -> ChunkedDecoder {
+        ChunkedDecoder {
+            state: State::Size,
+            max_line: DEFAULT_MAX_CHUNK_LINE,
+            scanned: 0,
+            trailers: HeadParser::new().max_head_size(DEFAULT_MAX_TRAILER_SIZE),
+        }
+    }
+
+    /// longest accepted chunk size line, including extensions and the line break
+    pub const fn max_chunk_line(mut self, max: usize) -> Self {
+        self.max_line = max;
+        self
+    }
+
+    /// the parser and limits used for the trailer section
+    pub fn trailer_parser(mut self, parser: HeadParser) -> Self {
+        self.trailers = parser;
+        self
+    }
+
+    /// returns `true` once the trailer section has been handed out
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Prepares the decoder for the next body
+    pub fn reset(&mut self) {
+        self.state = State::Size;
+        self.scanned = 0;
+        self.trailers.reset();
+    }
+
+    /// Decodes as far as the readable bytes allow.
+    /// Returns `None` when more bytes are needed or the body is done.
+    pub fn decode<'a>(&'a mut self, src: &'a mut WaterBuffer<u8>) -> Result<Option<Decoded<'a>>, ChunkedError> {
+        loop {
+            match self.state {
+                State::Size => {
+                    let Some((size, consumed)) = self.size_line(&src[..])? else {
+                        return Ok(None);
+                    };
+                    src.advance(consumed);
+                    self.state = if size == 0 { State::Trailers } else { State::Data(size) };
+                }
+                State::Data(remaining) => {
+                    if src.is_empty() {
+                        return Ok(None);
+                    }
+                    let len = (src.len() as u64).min(remaining) as usize;
+                    let remaining = remaining - len as u64;
+                    self.state = if remaining == 0 { State::DataEnd } else { State::Data(remaining) };
+                    return Ok(Some(Decoded::Data(Frame::new(src, 0, len, len))));
+                }
+                State::DataEnd => {
+                    let consumed = match src[..] {
+                        [] | [b'\r'] => return Ok(None),
+                        [b'\n', ..] => 1,
+                        [b'\r', b'\n', ..] => 2,
+                        _ => return Err(ChunkedError::MissingLineBreak),
+                    };
+                    src.advance(consumed);
+                    self.state = State::Size;
+                }
+                State::Trailers => {
+                    return match self.trailers.parse_fields(src) {
+                        Ok(Status::Partial) => Ok(None),
+                        Ok(Status::Complete(fields)) => {
+                            self.state = State::Done;
+                            Ok(Some(Decoded::Trailers(fields)))
+                        }
+                        Err(error) => Err(ChunkedError::Trailers(error)),
+                    };
+                }
+                State::Done => return Ok(None),
+            }
+        }
+    }
+
+    /// Appends every readable chunk byte to `dst`, dropping the trailers.
+    /// Returns `true` once the whole body has been decoded.
+    pub fn decode_into(&mut self, src: &mut WaterBuffer<u8>, dst: &mut WaterBuffer<u8>) -> Result<bool, ChunkedError> {
+        loop {
+            match self.decode(src)? {
+                Some(Decoded::Data(data)) => dst.extend_from_slice(&data),
+                Some(Decoded::Trailers(_)) => return Ok(true),
+                None => break,
+            }
+        }
+        Ok(self.is_done())
+    }
+
+    /// `chunk-size [ BWS chunk-ext ] CRLF`, returning the size and the bytes the line occupies
+    fn size_line(&mut self, readable: &[u8]) -> Result<Option<(u64, usize)>, ChunkedError> {
+        let Some(pos) = find_byte(&readable[self.scanned..], b'\n') else {
+            self.scanned = readable.len();
+            if readable.len() >= self.max_line {
+                return Err(ChunkedError::LineTooLong { max: self.max_line });
+            }
+            return Ok(None);
+        };
+        let newline = self.scanned + pos;
+        self.scanned = 0;
+        if newline >= self.max_line {
+            return Err(ChunkedError::LineTooLong { max: self.max_line });
+        }
+        let line = match &readable[..newline] {
+            [line @ .., b'\r'] => line,
+            line => line,
+        };
+        let digits = line.iter().take_while(|b| b.is_ascii_hexdigit()).count();
+        if digits == 0 {
+            return Err(ChunkedError::InvalidSize);
+        }
+        let mut size = 0u64;
+        for &digit in &line[..digits] {
+            let value = (digit as char).to_digit(16).unwrap() as u64;
+            size = size
+                .checked_mul(16)
+                .and_then(|size| size.checked_add(value))
+                .ok_or(ChunkedError::SizeOverflow)?;
+        }
+        let extension = &line[digits..];
+        let extension = &extension[extension.iter().take_while(|&&b| b == b' ' || b == b'\t').count()..];
+        match extension.first() {
+            None => {}
+            Some(b';') if is_field_text(extension) => {}
+            Some(b';') => return Err(ChunkedError::InvalidExtension),
+            Some(_) => return Err(ChunkedError::InvalidSize),
+        }
+        Ok(Some((size, newline + 1)))
+    }
+}
+
+/// Marks a chunk whose size is written by [`ChunkedEncoder::finish_chunk`]
+#[derive(Debug, PartialEq, Eq)]
+#[must_use = "the chunk size stays zero unless the chunk is finished"]
+pub struct ChunkHandle {
+    offset: usize,
+    width: usize,
+}
+
+impl ChunkHandle {
+    /// logical offset of the size digits inside the readable window
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// Error of [`ChunkedEncoder::finish_chunk`], handing the chunk back so it
+/// can still be cancelled
+#[derive(Debug, PartialEq, Eq)]
+pub struct FinishChunkError {
+    handle: ChunkHandle,
+    error: ChunkedError,
+}
+
+impl FinishChunkError {
+    /// why the size could not be written
+    pub const fn error(&self) -> ChunkedError {
+        self.error
+    }
+
+    /// the handle of the still unfinished chunk
+    pub fn into_handle(self) -> ChunkHandle {
+        self.handle
+    }
+}
+
+impl fmt::Display for FinishChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for FinishChunkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Writes chunked framing into a `WaterBuffer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkedEncoder {
+    size_width: usize,
+}
+
+impl Default for ChunkedEncoder {
+    fn default() -> Self {
+        ChunkedEncoder::new()
+    }
+}
+
+impl ChunkedEncoder {
+    /// creating an encoder reserving [`DEFAULT_SIZE_WIDTH`] hex digits per chunk
+    pub const fn new() -> ChunkedEncoder {
+        ChunkedEncoder {
+            size_width: DEFAULT_SIZE_WIDTH,
+        }
+    }
+
+    /// number of hex digits reserved by [`ChunkedEncoder::begin_chunk`]
+    ///
+    /// # Panics
+    /// when `width` is not between 1 and 16
+    pub const fn size_width(mut self, width: usize) -> Self {
+        assert!(width >= 1 && width <= 16, "Invalid chunk size width");
+        self.size_width = width;
+        self
+    }
+
+    /// Reserves a zero-padded chunk size at the current write position,
+    /// the chunk data is written right after it
+    pub fn begin_chunk(&self, dst: &mut WaterBuffer<u8>) -> ChunkHandle {
+        let offset = dst.len();
+        let reserved = self.size_width + 2;
+        dst.reserve(reserved);
+        let chunk = &mut dst.chunk_mut()[..reserved];
+        chunk[..self.size_width].fill(b'0');
+        chunk[self.size_width..].copy_from_slice(b"\r\n");
+        dst.advance_mut(reserved);
+        ChunkHandle {
+            offset,
+            width: self.size_width,
+        }
+    }
+
+    /// Writes the number of bytes following the size into it, closes the chunk
+    /// and returns the chunk length.
+    /// An empty chunk would end the body, so it is removed again instead.
+    /// On error the chunk stays in the buffer unfinished and the handle is returned
+    /// inside the error, e.g. for [`ChunkedEncoder::cancel_chunk`].
+    pub fn finish_chunk(&self, dst: &mut WaterBuffer<u8>, handle: ChunkHandle) -> Result<usize, FinishChunkError> {
+        let data_start = handle.offset + handle.width + 2;
+        assert!(data_start <= dst.len(), "Chunk size is out of bounds");
+        let len = dst.len() - data_start;
+        if len == 0 {
+            dst.truncate(handle.offset);
+            return Ok(0);
+        }
+        let max = u64::MAX >> (64 - 4 * handle.width);
+        if len as u64 > max {
+            let error = ChunkedError::ChunkTooLarge { len, max };
+            return Err(FinishChunkError { handle, error });
+        }
+        write_hex(len as u64, &mut dst[handle.offset..handle.offset + handle.width]);
+        dst.extend_from_slice(b"\r\n");
+        Ok(len)
+    }
+
+    /// Drops an unfinished chunk, including its size, from the buffer
+    pub fn cancel_chunk(&self, dst: &mut WaterBuffer<u8>, handle: ChunkHandle) {
+        dst.truncate(handle.offset);
+    }
+
+    /// Writes `data` as one chunk, nothing is written for empty data
+    pub fn encode_chunk(&self, data: &[u8], dst: &mut WaterBuffer<u8>) {
+        if data.is_empty() {
+            return;
+        }
+        let mut digits = [0u8; 16];
+        let width = 16 - (data.len() as u64).leading_zeros() as usize / 4;
+        write_hex(data.len() as u64, &mut digits[..width]);
+        dst.reserve(width + data.len() + 4);
+        dst.extend_from_slice(&digits[..width]);
+        dst.extend_from_slice(b"\r\n");
+        dst.extend_from_slice(data);
+        dst.extend_from_slice(b"\r\n");
+    }
+
+    /// Writes the last chunk and an empty trailer section
+    pub fn finish(&self, dst: &mut WaterBuffer<u8>) {
+        dst.extend_from_slice(b"0\r\n\r\n");
+    }
+
+    /// Writes the last chunk followed by `trailers`
+    pub fn finish_with_trailers(&self, dst: &mut WaterBuffer<u8>, trailers: &[(&[u8], &[u8])]) {
+        dst.extend_from_slice(b"0\r\n");
+        for (name, value) in trailers {
+            dst.extend_from_slice(name);
+            dst.extend_from_slice(b": ");
+            dst.extend_from_slice(value);
+            dst.extend_from_slice(b"\r\n");
+        }
+        dst.extend_from_slice(b"\r\n");
+    }
+}
+
+impl WaterEncoder<&[u8]> for ChunkedEncoder {
+    type Error = io::Error;
+
+    /// writes `item` as one chunk
+    fn encode(&mut self, item: &[u8], dst: &mut WaterBuffer<u8>) -> Result<(), Self::Error> {
+        self.encode_chunk(item, dst);
+        Ok(())
+    }
+}
+
+/// writes `value` as lowercase hex, zero-padded to `out.len()` digits
+fn write_hex(mut value: u64, out: &mut [u8]) {
+    for digit in out.iter_mut().rev() {
+        *digit = b"0123456789abcdef"[(value & 0xf) as usize];
+        value >>= 4;
+    }
+}
@@ -0,0 +1,367 @@
+//! Incremental request and response head parsing.
+//!
+//! The parser only keeps how far it has scanned and how many lines it has seen, so a head
+//! arriving one byte at a time costs a single pass over its bytes plus one final pass that
+//! splits the lines into offsets. The bytes in front of the scan position must stay in the
+//! buffer between calls, nothing may be consumed while a head is partial.
+
+use std::fmt;
+use std::ops::Range;
+
+use super::{is_field_text, is_token, HeadError, Status, Version};
+use crate::buffer::search::find_byte;
+use crate::WaterBuffer;
+
+/// default maximum number of header fields
+pub const DEFAULT_MAX_HEADERS: usize = 100;
+
+/// default maximum head size including the start line, 64 KiB
+pub const DEFAULT_MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Offsets of one header field in the readable window
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HeaderSpan {
+    /// the field name
+    pub name: Range<usize>,
+    /// the field value without surrounding whitespace
+    pub value: Range<usize>,
+}
+
+/// Offsets of a request line in the readable window
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestLine {
+    /// the method token
+    pub method: Range<usize>,
+    /// the request target
+    pub target: Range<usize>,
+    /// the protocol version
+    pub version: Version,
+}
+
+/// A parsed status line, the reason phrase as offsets into the readable window
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StatusLine {
+    /// the protocol version
+    pub version: Version,
+    /// the three digit status code
+    pub status: u16,
+    /// the reason phrase, may be empty
+    pub reason: Range<usize>,
+}
+
+/// A complete head borrowed from the read buffer.
+/// Every accessor resolves offsets against the readable window, the whole head,
+/// including the final empty line, is consumed from the buffer when dropped.
+pub struct Head<'a, L> {
+    buffer: &'a mut WaterBuffer<u8>,
+    line: L,
+    headers: &'a [HeaderSpan],
+    len: usize,
+}
+
+/// a parsed request head
+pub type RequestHead<'a> = Head<'a, RequestLine>;
+
+/// a parsed response head
+pub type ResponseHead<'a> = Head<'a, StatusLine>;
+
+/// a header section without a start line, e.g. the trailers of a chunked body
+pub type Fields<'a> = Head<'a, ()>;
+
+impl<L> Head<'_, L> {
+    /// returning the offsets of the start line
+    pub fn line(&self) -> &L {
+        &self.line
+    }
+
+    /// number of bytes the head occupies, consumed on drop
+    pub fn head_len(&self) -> usize {
+        self.len
+    }
+
+    /// every byte of the head
+    pub fn raw(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+
+    /// returning the offsets of the header fields in order
+    pub fn header_spans(&self) -> &[HeaderSpan] {
+        self.headers
+    }
+
+    /// Iterates the header fields as `(name, value)` in order
+    pub fn headers(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.headers
+            .iter()
+            .map(|header| (&self.buffer[header.name.clone()], &self.buffer[header.value.clone()]))
+    }
+
+    /// returning the value of the first header called `name`, compared case-insensitively
+    pub fn header(&self, name: &[u8]) -> Option<&[u8]> {
+        self.headers()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+}
+
+impl Head<'_, RequestLine> {
+    /// returning the method
+    pub fn method(&self) -> &[u8] {
+        &self.buffer[self.line.method.clone()]
+    }
+
+    /// returning the request target
+    pub fn target(&self) -> &[u8] {
+        &self.buffer[self.line.target.clone()]
+    }
+
+    /// returning the protocol version
+    pub fn version(&self) -> Version {
+        self.line.version
+    }
+}
+
+impl Head<'_, StatusLine> {
+    /// returning the protocol version
+    pub fn version(&self) -> Version {
+        self.line.version
+    }
+
+    /// returning the status code
+    pub fn status(&self) -> u16 {
+        self.line.status
+    }
+
+    /// returning the reason phrase
+    pub fn reason(&self) -> &[u8] {
+        &self.buffer[self.line.reason.clone()]
+    }
+}
+
+impl<L: fmt::Debug> fmt::Debug for Head<'_, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Head")
+            .field("line", &self.line)
+            .field("headers", &self.headers)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<L> Drop for Head<'_, L> {
+    fn drop(&mut self) {
+        self.buffer.advance(self.len);
+    }
+}
+
+/// Incremental HTTP/1.x head parser
+#[derive(Debug, Clone)]
+pub struct HeadParser {
+    max_headers: usize,
+    max_head_size: usize,
+    /// readable offset of the start line, empty lines in front of it are skipped
+    start: usize,
+    /// readable offset of the first line not seen complete yet
+    scanned: usize,
+    /// complete non-empty lines before `scanned`
+    lines: usize,
+    /// reused for the offsets of every head
+    headers: Vec<HeaderSpan>,
+}
+
+impl Default for HeadParser {
+    fn default() -> Self {
+        HeadParser::new()
+    }
+}
+
+impl HeadParser {
+    /// creating a parser with [`DEFAULT_MAX_HEADERS`] and [`DEFAULT_MAX_HEAD_SIZE`]
+    pub const fn new() -> HeadParser {
+        HeadParser {
+            max_headers: DEFAULT_MAX_HEADERS,
+            max_head_size: DEFAULT_MAX_HEAD_SIZE,
+            start: 0,
+            scanned: 0,
+            lines: 0,
+            headers: Vec::new(),
+        }
+    }
+
+    /// largest accepted number of header fields
+    pub const fn max_headers(mut self, max: usize) -> Self {
+        self.max_headers = max;
+        self
+    }
+
+    /// largest accepted head in bytes, including the start line and the final empty line
+    pub const fn max_head_size(mut self, max: usize) -> Self {
+        self.max_head_size = max;
+        self
+    }
+
+    /// Forgets a partially scanned head, needed before parsing from a different buffer
+    pub fn reset(&mut self) {
+        self.start = 0;
+        self.scanned = 0;
+        self.lines = 0;
+        self.headers.clear();
+    }
+
+    /// Parses a request head from the readable window of `buffer`.
+    /// Returns [`Status::Partial`] until the whole head has arrived.
+    pub fn parse_request<'a>(
+        &'a mut self,
+        buffer: &'a mut WaterBuffer<u8>,
+    ) -> Result<Status<RequestHead<'a>>, HeadError> {
+        self.parse(buffer, true, parse_request_line)
+    }
+
+    /// Parses a response head from the readable window of `buffer`.
+    /// Returns [`Status::Partial`] until the whole head has arrived.
+    pub fn parse_response<'a>(
+        &'a mut self,
+        buffer: &'a mut WaterBuffer<u8>,
+    ) -> Result<Status<ResponseHead<'a>>, HeadError> {
+        self.parse(buffer, true, parse_status_line)
+    }
+
+    /// Parses header fields up to and including the empty line without a start line,
+    /// as found in the trailer section of a chunked body.
+    /// Returns [`Status::Partial`] until the whole section has arrived.
+    pub fn parse_fields<'a>(&'a mut self, buffer: &'a mut WaterBuffer<u8>) -> Result<Status<Fields<'a>>, HeadError> {
+        self.parse(buffer, false, |_, _| Ok(()))
+    }
+
+    /// Scans for the end of the head, then splits it into the start line and header offsets
+    fn parse<'a, L>(
+        &'a mut self,
+        buffer: &'a mut WaterBuffer<u8>,
+        has_start_line: bool,
+        start_line: fn(&[u8], Range<usize>) -> Result<L, HeadError>,
+    ) -> Result<Status<Head<'a, L>>, HeadError> {
+        let len = match self.scan(&buffer[..], has_start_line) {
+            Ok(Some(len)) => len,
+            Ok(None) => return Ok(Status::Partial),
+            Err(error) => {
+                self.reset();
+                return Err(error);
+            }
+        };
+        let start = self.start;
+        self.reset();
+        let head = &buffer[..len];
+        let (line, mut pos) = if has_start_line { next_line(head, start) } else { (start..start, start) };
+        let line = start_line(head, line)?;
+        loop {
+            let (field, next) = next_line(head, pos);
+            if field.is_empty() {
+                break;
+            }
+            self.headers.push(parse_header(head, field)?);
+            pos = next;
+        }
+        Ok(Status::Complete(Head {
+            buffer,
+            line,
+            headers: &self.headers,
+            len,
+        }))
+    }
+
+    /// Continues scanning line by line, returning the head length once the empty line is found
+    fn scan(&mut self, readable: &[u8], has_start_line: bool) -> Result<Option<usize>, HeadError> {
+        loop {
+            let Some(pos) = find_byte(&readable[self.scanned..], b'\n') else {
+                if readable.len() > self.max_head_size {
+                    return Err(HeadError::TooLarge { max: self.max_head_size });
+                }
+                return Ok(None);
+            };
+            let end = self.scanned + pos + 1;
+            if end > self.max_head_size {
+                return Err(HeadError::TooLarge { max: self.max_head_size });
+            }
+            let empty = pos == 0 || (pos == 1 && readable[self.scanned] == b'\r');
+            self.scanned = end;
+            if !empty {
+                self.lines += 1;
+                if self.lines > self.max_headers + has_start_line as usize {
+                    return Err(HeadError::TooManyHeaders { max: self.max_headers });
+                }
+            } else if self.lines == 0 && has_start_line {
+                // robustness for clients sending an extra CRLF after the previous message
+                self.start = end;
+            } else {
+                return Ok(Some(end));
+            }
+        }
+    }
+}
+
+/// Returns the line starting at `pos` without its line ending and the offset of the next line.
+/// The head is complete, so every line ends with `\n`.
+fn next_line(head: &[u8], pos: usize) -> (Range<usize>, usize) {
+    let newline = pos + find_byte(&head[pos..], b'\n').unwrap();
+    let end = if newline > pos && head[newline - 1] == b'\r' { newline - 1 } else { newline };
+    (pos..end, newline + 1)
+}
+
+/// `method SP request-target SP HTTP-version`
+fn parse_request_line(head: &[u8], line: Range<usize>) -> Result<RequestLine, HeadError> {
+    let bytes = &head[line.clone()];
+    let method_end = find_byte(bytes, b' ').ok_or(HeadError::InvalidRequestLine)?;
+    let target_end = method_end + 1
+        + find_byte(&bytes[method_end + 1..], b' ').ok_or(HeadError::InvalidRequestLine)?;
+    let target = &bytes[method_end + 1..target_end];
+    if !is_token(&bytes[..method_end]) || target.is_empty() || !target.iter().all(|&b| b > b' ' && b < 0x7f) {
+        return Err(HeadError::InvalidRequestLine);
+    }
+    let version = Version::parse(&bytes[target_end + 1..]).ok_or(HeadError::InvalidVersion)?;
+    Ok(RequestLine {
+        method: line.start..line.start + method_end,
+        target: line.start + method_end + 1..line.start + target_end,
+        version,
+    })
+}
+
+/// `HTTP-version SP 3DIGIT [SP reason-phrase]`
+fn parse_status_line(head: &[u8], line: Range<usize>) -> Result<StatusLine, HeadError> {
+    let bytes = &head[line.clone()];
+    let version_end = find_byte(bytes, b' ').ok_or(HeadError::InvalidStatusLine)?;
+    let version = Version::parse(&bytes[..version_end]).ok_or(HeadError::InvalidVersion)?;
+    let rest = &bytes[version_end + 1..];
+    let code = rest.get(..3).ok_or(HeadError::InvalidStatusLine)?;
+    if !code.iter().all(u8::is_ascii_digit) {
+        return Err(HeadError::InvalidStatusLine);
+    }
+    let status = code.iter().fold(0u16, |status, digit| status * 10 + (digit - b'0') as u16);
+    let reason = match rest.get(3) {
+        None => line.end..line.end,
+        Some(b' ') if is_field_text(&rest[4..]) => line.start + version_end + 5..line.end,
+        Some(_) => return Err(HeadError::InvalidStatusLine),
+    };
+    Ok(StatusLine { version, status, reason })
+}
+
+/// `field-name ":" OWS field-value OWS`, folded lines are rejected
+fn parse_header(head: &[u8], line: Range<usize>) -> Result<HeaderSpan, HeadError> {
+    let bytes = &head[line.clone()];
+    let colon = find_byte(bytes, b':').ok_or(HeadError::InvalidHeader)?;
+    if !is_token(&bytes[..colon]) {
+        return Err(HeadError::InvalidHeader);
+    }
+    let is_space = |b: &u8| *b == b' ' || *b == b'\t';
+    let value = &bytes[colon + 1..];
+    let leading = value.iter().take_while(|b| is_space(b)).count();
+    let trailing = value[leading..].iter().rev().take_while(|b| is_space(b)).count();
+    let value = &value[leading..value.len() - trailing];
+    if !is_field_text(value) {
+        return Err(HeadError::InvalidHeader);
+    }
+    let value_start = line.start + colon + 1 + leading;
+    Ok(HeaderSpan {
+        name: line.start..line.start + colon,
+        value: value_start..value_start + value.len(),
+    })
+}
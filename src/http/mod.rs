@@ -0,0 +1,151 @@
+//! HTTP/1.x parsing working directly on a `WaterBuffer` read buffer.
+//!
+//! [`HeadParser`] parses request and response heads incrementally: it returns
+//! [`Status::Partial`] until the empty line ending the head has arrived and never
+//! rescans lines it has already seen. A complete head is handed out as offsets into the
+//! readable window together with the buffer, the head bytes are consumed with `advance`
+//! once the parsed head is dropped, leaving the body at the front of the buffer.
+//!
+//! Chunked bodies are decoded with [`ChunkedDecoder`] and written with [`ChunkedEncoder`].
+
+use std::fmt;
+use std::io;
+
+mod chunked;
+mod head;
+
+pub use chunked::*;
+pub use head::*;
+
+/// Outcome of an incremental parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status<T> {
+    /// everything needed has been parsed
+    Complete(T),
+    /// more bytes are needed
+    Partial,
+}
+
+impl<T> Status<T> {
+    /// returns `true` for [`Status::Complete`]
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Status::Complete(_))
+    }
+
+    /// returns `true` for [`Status::Partial`]
+    pub fn is_partial(&self) -> bool {
+        matches!(self, Status::Partial)
+    }
+
+    /// returning the complete value, `None` when partial
+    pub fn complete(self) -> Option<T> {
+        match self {
+            Status::Complete(value) => Some(value),
+            Status::Partial => None,
+        }
+    }
+}
+
+/// Protocol version of a request or response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Version {
+    /// `HTTP/1.0`
+    Http10,
+    /// `HTTP/1.1`
+    Http11,
+}
+
+impl Version {
+    /// parses `HTTP/1.0` or `HTTP/1.1`
+    fn parse(bytes: &[u8]) -> Option<Version> {
+        match bytes {
+            b"HTTP/1.1" => Some(Version::Http11),
+            b"HTTP/1.0" => Some(Version::Http10),
+            _ => None,
+        }
+    }
+
+    /// returning the version as written on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Version::Http10 => "HTTP/1.0",
+            Version::Http11 => "HTTP/1.1",
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Errors returned while parsing a head
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadError {
+    /// the head is larger than the configured maximum
+    TooLarge {
+        /// configured maximum
+        max: usize,
+    },
+    /// the head holds more header fields than the configured maximum
+    TooManyHeaders {
+        /// configured maximum
+        max: usize,
+    },
+    /// the method, request target or the shape of the request line is invalid
+    InvalidRequestLine,
+    /// the status code or the shape of the status line is invalid
+    InvalidStatusLine,
+    /// the version is neither `HTTP/1.0` nor `HTTP/1.1`
+    InvalidVersion,
+    /// a header name or value contains invalid bytes, or a line is folded
+    InvalidHeader,
+}
+
+impl fmt::Display for HeadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeadError::TooLarge { max } => write!(f, "head exceeds the maximum of {} bytes", max),
+            HeadError::TooManyHeaders { max } => write!(f, "head exceeds the maximum of {} headers", max),
+            HeadError::InvalidRequestLine => f.write_str("invalid request line"),
+            HeadError::InvalidStatusLine => f.write_str("invalid status line"),
+            HeadError::InvalidVersion => f.write_str("invalid HTTP version"),
+            HeadError::InvalidHeader => f.write_str("invalid header field"),
+        }
+    }
+}
+
+impl std::error::Error for HeadError {}
+
+impl From<HeadError> for io::Error {
+    fn from(error: HeadError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// `tchar` from RFC 9110, the bytes allowed in methods and header names
+const fn token_table() -> [bool; 256] {
+    let mut table = [false; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        table[byte] = matches!(byte as u8,
+            b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z'
+            | b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*'
+            | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~');
+        byte += 1;
+    }
+    table
+}
+
+static TOKEN: [bool; 256] = token_table();
+
+/// returns `true` when `bytes` is a non-empty token
+fn is_token(bytes: &[u8]) -> bool {
+    !bytes.is_empty() && bytes.iter().all(|&b| TOKEN[b as usize])
+}
+
+/// field values and reason phrases: visible characters, spaces, tabs and obs-text
+fn is_field_text(bytes: &[u8]) -> bool {
+    bytes.iter().all(|&b| b == b'\t' || (b >= b' ' && b != 0x7f))
+}
@@ -1,5 +1,17 @@
 mod buffer;
 mod tests;
+/// async read / write helpers for tokio and futures-io
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+pub mod async_io;
+/// frame codecs working directly on `WaterBuffer`
+#[cfg(not(feature = "circular_buffer"))]
+pub mod codec;
+/// incremental HTTP/1.x parsing over `WaterBuffer`
+#[cfg(not(feature = "circular_buffer"))]
+pub mod http;
+/// zero-copy transfers between file descriptors on Linux
+#[cfg(all(target_os = "linux", feature = "zero-copy"))]
+pub mod transfer;
 /// for providing helping functionalities
 #[cfg(feature = "uring")]
 pub mod helper;
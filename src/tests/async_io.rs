@@ -0,0 +1,191 @@
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::super::super::async_io::tokio_ext::{WaterReadExt, WaterWriteExt};
+    use super::super::super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // ============================================================================
+    // TOKIO READ TESTS
+    // ============================================================================
+
+    #[tokio::test]
+    async fn test_tokio_read_into_spare_capacity() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(b"hello world").await.unwrap();
+        drop(client);
+
+        let mut buffer = WaterBuffer::with_capacity(4);
+        buffer.extend_from_slice(b">");
+        let mut total = 0;
+        loop {
+            let n = server.read_buf_water(&mut buffer).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            total += n;
+        }
+        assert_eq!(total, 11);
+        assert_eq!(buffer, b">hello world");
+    }
+
+    #[tokio::test]
+    async fn test_tokio_read_reserves_when_full() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(b"abc").await.unwrap();
+
+        let mut buffer = WaterBuffer::with_capacity(2);
+        buffer.extend_from_slice(b"xy");
+        let n = server.read_buf_water(&mut buffer).await.unwrap();
+        assert_eq!(n, 3);
+        assert!(buffer.capacity() > 2);
+        assert_eq!(buffer, b"xyabc");
+    }
+
+    // ============================================================================
+    // TOKIO WRITE TESTS
+    // ============================================================================
+
+    #[tokio::test]
+    async fn test_tokio_write_all_consumes_buffer() {
+        let (mut client, mut server) = tokio::io::duplex(4);
+        let mut buffer = WaterBuffer::from(b"a longer message than the pipe".as_slice());
+        let write = async move {
+            client.write_all_water(&mut buffer).await.unwrap();
+            assert!(buffer.is_empty());
+        };
+        let read = async move {
+            let mut out = Vec::new();
+            server.read_to_end(&mut out).await.unwrap();
+            out
+        };
+        let (_, out) = tokio::join!(write, read);
+        assert_eq!(out, b"a longer message than the pipe");
+    }
+
+    #[tokio::test]
+    async fn test_tokio_write_once_advances() {
+        let (mut client, mut server) = tokio::io::duplex(4);
+        let mut buffer = WaterBuffer::from(b"abcdefgh".as_slice());
+        let n = client.write_water(&mut buffer).await.unwrap();
+        assert_eq!(n, 4);
+        assert_eq!(buffer, b"efgh");
+
+        let mut out = [0u8; 4];
+        server.read_exact(&mut out).await.unwrap();
+        assert_eq!(&out, b"abcd");
+    }
+
+    #[tokio::test]
+    async fn test_tokio_write_empty_buffer() {
+        let (mut client, _server) = tokio::io::duplex(4);
+        let mut buffer = WaterBuffer::<u8>::with_capacity(4);
+        assert_eq!(client.write_water(&mut buffer).await.unwrap(), 0);
+        client.write_all_water(&mut buffer).await.unwrap();
+    }
+}
+
+#[cfg(all(test, feature = "futures-io"))]
+mod futures_tests {
+    use super::super::super::async_io::futures_ext::{WaterReadExt, WaterWriteExt};
+    use super::super::super::*;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+
+    // ============================================================================
+    // FUTURES-IO TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_futures_read_until_eof() {
+        block_on(async {
+            let mut reader = Cursor::new(vec![7u8; 20_000]);
+            let mut buffer = WaterBuffer::with_capacity(16);
+            while reader.read_buf_water(&mut buffer).await.unwrap() != 0 {}
+            assert_eq!(buffer.len(), 20_000);
+            assert!(buffer.iter().all(|&b| b == 7));
+        });
+    }
+
+    /// reader checking that it is only ever handed zeroed, initialized bytes
+    struct ZeroChecking;
+
+    impl futures::io::AsyncRead for ZeroChecking {
+        fn poll_read(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            assert!(buf.iter().all(|&b| b == 0), "reader was handed stale bytes");
+            buf[..3].copy_from_slice(b"new");
+            std::task::Poll::Ready(Ok(3))
+        }
+    }
+
+    #[test]
+    fn test_futures_read_hands_out_zeroed_spare() {
+        block_on(async {
+            let mut buffer = WaterBuffer::with_capacity(16);
+            buffer.extend_from_slice(b"stale bytes");
+            buffer.clear();
+            assert_eq!(ZeroChecking.read_buf_water(&mut buffer).await.unwrap(), 3);
+            assert_eq!(buffer, "new");
+        });
+    }
+
+    /// reader scribbling over its slice and asking to be polled again once
+    struct PendingOnce(bool);
+
+    impl futures::io::AsyncRead for PendingOnce {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            if !self.0 {
+                self.0 = true;
+                buf.fill(b'x');
+                cx.waker().wake_by_ref();
+                return std::task::Poll::Pending;
+            }
+            // the tail zeroed for the first poll is not zeroed again
+            assert!(buf.iter().all(|&b| b == b'x'), "spare capacity was zeroed twice");
+            std::task::Poll::Ready(Ok(buf.len()))
+        }
+    }
+
+    #[test]
+    fn test_futures_read_zeroes_spare_once() {
+        block_on(async {
+            let mut buffer = WaterBuffer::with_capacity(4096);
+            assert_eq!(PendingOnce(false).read_buf_water(&mut buffer).await.unwrap(), 4096);
+            assert!(buffer.iter().all(|&b| b == b'x'));
+        });
+    }
+
+    #[test]
+    fn test_futures_write_all_and_once() {
+        block_on(async {
+            let mut writer = Cursor::new(Vec::new());
+            let mut buffer = WaterBuffer::from("head body");
+            buffer.advance(5);
+            writer.write_all_water(&mut buffer).await.unwrap();
+            assert!(buffer.is_empty());
+
+            let mut buffer = WaterBuffer::from("!");
+            assert_eq!(writer.write_water(&mut buffer).await.unwrap(), 1);
+            assert_eq!(writer.into_inner(), b"body!");
+        });
+    }
+
+    #[test]
+    fn test_futures_write_zero_is_an_error() {
+        block_on(async {
+            let mut out = [0u8; 2];
+            let mut writer = Cursor::new(&mut out[..]);
+            let mut buffer = WaterBuffer::from("abc");
+            let error = writer.write_all_water(&mut buffer).await.unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::WriteZero);
+            assert_eq!(buffer, b"c");
+        });
+    }
+}
@@ -0,0 +1,169 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::*;
+    use std::io::{self, Cursor, IoSlice, Write};
+    use std::sync::Arc;
+
+    /// writer accepting at most `limit` bytes per call
+    struct Trickle {
+        out: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.limit);
+            self.out.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+            let mut written = 0;
+            for buf in bufs {
+                let n = self.write(&buf[..buf.len().min(self.limit - written)])?;
+                written += n;
+                if written == self.limit {
+                    break;
+                }
+            }
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn response() -> WaterChain {
+        let mut chain = WaterChain::new();
+        chain.push(WaterBuffer::from("HTTP/1.1 200 OK\r\n\r\n"));
+        chain.push(Arc::<[u8]>::from(&b"shared body"[..]));
+        chain.push(" and static tail");
+        chain
+    }
+
+    // ============================================================================
+    // GATHER TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_as_io_slices_skips_empty_segments() {
+        let mut chain = response();
+        chain.push(WaterBuffer::with_capacity(8));
+        let mut slices = [IoSlice::new(&[]); 8];
+        assert_eq!(chain.as_io_slices(&mut slices), 3);
+        assert_eq!(&*slices[1], b"shared body");
+        assert_eq!(chain.len(), 19 + 11 + 16);
+
+        let mut two = [IoSlice::new(&[]); 2];
+        assert_eq!(chain.as_io_slices(&mut two), 2);
+    }
+
+    #[test]
+    fn test_advance_across_segments() {
+        let mut chain = response();
+        chain.advance(19 + 7);
+        assert_eq!(chain.segment_count(), 2);
+        assert_eq!(chain.segments().next().unwrap().as_slice(), b"body");
+        chain.advance(4 + 5);
+        assert_eq!(chain.segments().next().unwrap().as_slice(), b"static tail");
+        chain.advance(11);
+        assert!(chain.is_empty());
+        assert_eq!(chain.segment_count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient space to advance")]
+    fn test_advance_past_end_panics() {
+        let mut chain = response();
+        chain.advance(1000);
+    }
+
+    #[test]
+    fn test_write_vectored_with_partial_writes() {
+        let mut chain = response();
+        let mut writer = Trickle { out: Vec::new(), limit: 7 };
+        while !chain.is_empty() {
+            assert!(chain.write_vectored_to(&mut writer).unwrap() > 0);
+        }
+        assert_eq!(writer.out, b"HTTP/1.1 200 OK\r\n\r\nshared body and static tail");
+    }
+
+    // ============================================================================
+    // SCATTER TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_read_vectored_fills_segments_in_order() {
+        let mut chain: WaterChain = [WaterBuffer::with_capacity(4), WaterBuffer::with_capacity(16)]
+            .into_iter()
+            .collect();
+        let mut reader = Cursor::new(b"head and the rest".to_vec());
+        let mut total = 0;
+        loop {
+            let n = chain.read_vectored_from(&mut reader).unwrap();
+            if n == 0 {
+                break;
+            }
+            total += n;
+        }
+        assert_eq!(total, 17);
+        let mut segments = chain.segments();
+        assert_eq!(segments.next().unwrap().as_slice(), b"head");
+        assert_eq!(segments.next().unwrap().as_slice(), b" and the rest");
+    }
+
+    #[test]
+    fn test_advance_mut_skips_shared_segments() {
+        let mut chain = WaterChain::new();
+        chain.push(WaterBuffer::with_capacity(2));
+        chain.push("static");
+        chain.push(WaterBuffer::with_capacity(4));
+        let mut slices: Vec<io::IoSliceMut<'_>> = (0..4).map(|_| io::IoSliceMut::new(&mut [])).collect();
+        let count = chain.as_spare_io_slices_mut(&mut slices);
+        assert_eq!(count, 2);
+        slices[0][..2].copy_from_slice(b"ab");
+        slices[1][..3].copy_from_slice(b"cde");
+        chain.advance_mut(5);
+        let collected: Vec<u8> = chain.segments().flat_map(|s| s.as_slice().to_vec()).collect();
+        assert_eq!(collected, b"abstaticcde");
+    }
+
+    #[test]
+    fn test_spare_io_slices_are_zeroed() {
+        let mut stale = WaterBuffer::with_capacity(8);
+        stale.extend_from_slice(b"stale!");
+        stale.clear();
+        let mut chain = WaterChain::new();
+        chain.push(stale);
+        let mut slices = [io::IoSliceMut::new(&mut [])];
+        assert_eq!(chain.as_spare_io_slices_mut(&mut slices), 1);
+        assert_eq!(&*slices[0], &[0u8; 8]);
+    }
+
+    #[test]
+    fn test_spare_io_slices_are_zeroed_once() {
+        let mut chain = WaterChain::new();
+        chain.push(WaterBuffer::with_capacity(4));
+        chain.push(WaterBuffer::with_capacity(4));
+        {
+            let mut slices = [io::IoSliceMut::new(&mut []), io::IoSliceMut::new(&mut [])];
+            assert_eq!(chain.as_spare_io_slices_mut(&mut slices), 2);
+            slices[0].fill(b'a');
+            slices[1].fill(b'b');
+        }
+        // a readv that did not happen leaves the bytes as they are, they are not zeroed again
+        let mut slices = [io::IoSliceMut::new(&mut []), io::IoSliceMut::new(&mut [])];
+        assert_eq!(chain.as_spare_io_slices_mut(&mut slices), 2);
+        assert_eq!(&*slices[0], b"aaaa");
+        assert_eq!(&*slices[1], b"bbbb");
+    }
+
+    #[test]
+    #[should_panic(expected = "Chain capacity exceeded")]
+    fn test_advance_mut_past_capacity_panics() {
+        let mut chain = WaterChain::new();
+        chain.push(WaterBuffer::with_capacity(2));
+        chain.advance_mut(3);
+    }
+}
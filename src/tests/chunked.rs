@@ -0,0 +1,158 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::codec::WaterEncoder;
+    use super::super::super::http::*;
+    use super::super::super::*;
+
+    fn buffer_of(data: &[u8]) -> WaterBuffer<u8> {
+        let mut buffer = WaterBuffer::with_capacity(0);
+        buffer.extend_from_slice(data);
+        buffer
+    }
+
+    const BODY: &[u8] = b"4\r\nWiki\r\n5;name=value\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\nnext";
+
+    // ============================================================================
+    // DECODER TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_decode_whole_body() {
+        let mut src = buffer_of(BODY);
+        let mut decoder = ChunkedDecoder::new();
+        let mut data = Vec::new();
+        loop {
+            match decoder.decode(&mut src).unwrap() {
+                Some(Decoded::Data(chunk)) => data.extend_from_slice(&chunk),
+                Some(Decoded::Trailers(trailers)) => {
+                    assert_eq!(trailers.header(b"expires"), Some(&b"never"[..]));
+                }
+                None => break,
+            }
+        }
+        assert!(decoder.is_done());
+        assert_eq!(data, b"Wikipedia in\r\n\r\nchunks.");
+        // the bytes after the body are left alone
+        assert_eq!(&src[..], b"next");
+    }
+
+    #[test]
+    fn test_decode_byte_by_byte() {
+        let mut decoder = ChunkedDecoder::new();
+        let mut src = WaterBuffer::with_capacity(0);
+        let mut dst = WaterBuffer::with_capacity(0);
+        let body = &BODY[..BODY.len() - 4];
+        for (i, byte) in body.iter().enumerate() {
+            src.push(*byte);
+            let done = decoder.decode_into(&mut src, &mut dst).unwrap();
+            assert_eq!(done, i + 1 == body.len(), "after {} bytes", i + 1);
+        }
+        assert_eq!(&dst[..], b"Wikipedia in\r\n\r\nchunks.");
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn test_partial_chunk_is_handed_out() {
+        let mut decoder = ChunkedDecoder::new();
+        let mut src = buffer_of(b"a\r\n0123");
+        match decoder.decode(&mut src).unwrap() {
+            Some(Decoded::Data(chunk)) => assert_eq!(&chunk[..], b"0123"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(decoder.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(b"456789\r\n0\r\n\r\n");
+        let mut dst = WaterBuffer::with_capacity(0);
+        assert!(decoder.decode_into(&mut src, &mut dst).unwrap());
+        assert_eq!(&dst[..], b"456789");
+        assert!(decoder.decode(&mut src).unwrap().is_none());
+        decoder.reset();
+        assert!(!decoder.is_done());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let cases: [(&[u8], ChunkedError); 6] = [
+            (b"xyz\r\n", ChunkedError::InvalidSize),
+            (b"4x\r\n", ChunkedError::InvalidSize),
+            (b"4 ;\x01\r\n", ChunkedError::InvalidExtension),
+            (b"10000000000000000\r\n", ChunkedError::SizeOverflow),
+            (b"2\r\nabX\r\n", ChunkedError::MissingLineBreak),
+            (b"0\r\nBad Trailer\r\n\r\n", ChunkedError::Trailers(HeadError::InvalidHeader)),
+        ];
+        for (input, expected) in cases {
+            let mut src = buffer_of(input);
+            let mut dst = WaterBuffer::with_capacity(0);
+            let error = ChunkedDecoder::new().decode_into(&mut src, &mut dst).unwrap_err();
+            assert_eq!(error, expected, "{:?}", Escaped::new(input));
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut decoder = ChunkedDecoder::new().max_chunk_line(8);
+        let mut src = buffer_of(b"1;abcdefgh");
+        let mut dst = WaterBuffer::with_capacity(0);
+        assert_eq!(decoder.decode_into(&mut src, &mut dst).unwrap_err(), ChunkedError::LineTooLong { max: 8 });
+
+        let mut decoder = ChunkedDecoder::new().trailer_parser(HeadParser::new().max_headers(1));
+        let mut src = buffer_of(b"0\r\nA: 1\r\nB: 2\r\n\r\n");
+        let error = decoder.decode_into(&mut src, &mut dst).unwrap_err();
+        assert_eq!(error, ChunkedError::Trailers(HeadError::TooManyHeaders { max: 1 }));
+    }
+
+    // ============================================================================
+    // ENCODER TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_encode_chunks() {
+        let mut encoder = ChunkedEncoder::new();
+        let mut dst = WaterBuffer::with_capacity(0);
+        encoder.encode_chunk(b"Wiki", &mut dst);
+        encoder.encode_chunk(b"", &mut dst);
+        WaterEncoder::encode(&mut encoder, &[b'x'; 26][..], &mut dst).unwrap();
+        encoder.finish_with_trailers(&mut dst, &[(b"Expires", b"never")]);
+        let mut expected = b"4\r\nWiki\r\n1a\r\n".to_vec();
+        expected.extend_from_slice(&[b'x'; 26]);
+        expected.extend_from_slice(b"\r\n0\r\nExpires: never\r\n\r\n");
+        assert_eq!(&dst[..], &expected[..]);
+    }
+
+    #[test]
+    fn test_back_patched_chunk() {
+        let encoder = ChunkedEncoder::new();
+        let mut dst = buffer_of(b"HTTP/1.1 200 OK\r\n\r\n");
+        let head_len = dst.len();
+        let chunk = encoder.begin_chunk(&mut dst);
+        assert_eq!(chunk.offset(), head_len);
+        dst.extend_from_slice(&[b'y'; 300]);
+        assert_eq!(encoder.finish_chunk(&mut dst, chunk).unwrap(), 300);
+        // an empty chunk is dropped instead of ending the body
+        let empty = encoder.begin_chunk(&mut dst);
+        assert_eq!(encoder.finish_chunk(&mut dst, empty).unwrap(), 0);
+        encoder.finish(&mut dst);
+        assert_eq!(&dst[head_len..head_len + 10], b"0000012c\r\n");
+
+        dst.advance(head_len);
+        let mut decoded = WaterBuffer::with_capacity(0);
+        assert!(ChunkedDecoder::new().decode_into(&mut dst, &mut decoded).unwrap());
+        assert_eq!(&decoded[..], &[b'y'; 300][..]);
+    }
+
+    #[test]
+    fn test_chunk_too_large_and_cancel() {
+        let encoder = ChunkedEncoder::new().size_width(1);
+        let mut dst = WaterBuffer::with_capacity(0);
+        let chunk = encoder.begin_chunk(&mut dst);
+        dst.extend_from_slice(&[0; 16]);
+        let error = encoder.finish_chunk(&mut dst, chunk).unwrap_err();
+        assert_eq!(error.error(), ChunkedError::ChunkTooLarge { len: 16, max: 15 });
+        let chunk = encoder.begin_chunk(&mut dst);
+        dst.extend_from_slice(b"abc");
+        encoder.cancel_chunk(&mut dst, chunk);
+        assert_eq!(dst.len(), 3 + 16);
+        // the failed chunk can still be dropped through the returned handle
+        encoder.cancel_chunk(&mut dst, error.into_handle());
+        assert!(dst.is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod tests {
+    use super::super::super::*;
+
+    // ============================================================================
+    // DISPLAY TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_display_escapes() {
+        let b = WaterBuffer::from("GET / HTTP/1.1\r\n\"q\"\\\x01\x7f");
+        assert_eq!(b.to_string(), r#"b"GET / HTTP/1.1\r\n\"q\"\\\x01\x7f""#);
+    }
+
+    #[test]
+    fn test_display_truncates() {
+        let b = WaterBuffer::from("abcdefghij");
+        assert_eq!(format!("{:.4}", b), r#"b"abcd"... (+6 bytes)"#);
+        assert_eq!(b.escaped().limit(10).to_string(), r#"b"abcdefghij""#);
+
+        let big: WaterBuffer<u8> = std::iter::repeat_n(b'a', DEFAULT_FORMAT_LIMIT + 3).collect();
+        assert!(big.to_string().ends_with("\"... (+3 bytes)"));
+        assert!(!big.escaped().unlimited().to_string().contains("..."));
+    }
+
+    #[test]
+    fn test_display_starts_at_readable_window() {
+        let mut b = WaterBuffer::with_capacity(16);
+        b.extend_from_slice(b"skipkeep");
+        b.advance(4);
+        assert_eq!(b.to_string(), r#"b"keep""#);
+    }
+
+    // ============================================================================
+    // HEXDUMP TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_hexdump_rows() {
+        let b = WaterBuffer::from("GET / HTTP/1.1\r\nHost");
+        let expected = "\
+00000000  47 45 54 20 2f 20 48 54  54 50 2f 31 2e 31 0d 0a  |GET / HTTP/1.1..|
+00000010  48 6f 73 74                                       |Host|
+";
+        assert_eq!(format!("{:x}", b), expected);
+        assert_eq!(b.hexdump().to_string(), expected);
+    }
+
+    #[test]
+    fn test_hexdump_limit() {
+        let b: WaterBuffer<u8> = (0u8..40).collect();
+        let dump = format!("{:.16x}", b);
+        assert_eq!(dump.lines().count(), 2);
+        assert!(dump.ends_with("... (+24 bytes)\n"));
+        assert_eq!(HexDump::new(&[]).to_string(), "");
+    }
+
+    // ============================================================================
+    // DEBUG TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_debug_prints_contents() {
+        let mut b = WaterBuffer::with_capacity(8);
+        b.extend_from_slice(b"hi\n");
+        assert_eq!(
+            format!("{:?}", b),
+            r#"WaterBuffer { len: 3, cap: 8, start_pos: 0, data: b"hi\n" }"#
+        );
+    }
+
+    #[test]
+    fn test_debug_alternate_is_hexdump() {
+        let mut b = WaterBuffer::with_capacity(8);
+        b.extend_from_slice(b"hi");
+        assert_eq!(
+            format!("{:#?}", b),
+            "WaterBuffer { len: 2, cap: 8, start_pos: 0 }\n00000000  68 69                                             |hi|\n"
+        );
+    }
+
+    #[test]
+    fn test_debug_non_byte_elements() {
+        let mut values = std::mem::ManuallyDrop::new(vec![1u32, 2, 3, 4]);
+        values.shrink_to_fit();
+        let b: WaterBuffer<u32> = WaterBuffer {
+            cap: values.capacity(),
+            start_pos: 1,
+            #[cfg(feature = "circular_buffer")]
+            circular_position: None,
+            pointer: values.as_mut_ptr(),
+            #[cfg(feature = "unsafe_clone")]
+            original: None,
+            filled_data_length: 2,
+            initialized: 0,
+            #[cfg(all(unix, feature = "mmap"))]
+            page_mode: PageMode::Regular,
+        };
+        assert_eq!(
+            format!("{:?}", b),
+            "WaterBuffer { len: 2, cap: 4, start_pos: 1, data: [2, 3] }"
+        );
+    }
+}
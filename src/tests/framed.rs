@@ -0,0 +1,96 @@
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::super::super::codec::*;
+    use super::super::super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn test_framed_write_then_read() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut writer = WaterFramedWrite::new(client, LengthDelimitedCodec::new());
+        let mut reader = WaterFramedRead::with_capacity(server, LengthDelimitedCodec::new(), 4);
+
+        let write = async move {
+            for i in 0..100u32 {
+                let body = format!("frame number {}", i);
+                writer.send(body.as_bytes()).await.unwrap();
+            }
+            SinkExt::<&[u8]>::close(&mut writer).await.unwrap();
+        };
+        let read = async move {
+            let mut count = 0;
+            while let Some(frame) = reader.next().await {
+                assert_eq!(frame.unwrap(), format!("frame number {}", count).as_str());
+                count += 1;
+            }
+            count
+        };
+        let (_, count) = tokio::join!(write, read);
+        assert_eq!(count, 100);
+    }
+
+    #[tokio::test]
+    async fn test_framed_read_lines_with_eof() {
+        let (mut client, server) = tokio::io::duplex(8);
+        let mut reader = WaterFramedRead::new(server, LineDecoder::default());
+        let write = async move {
+            client.write_all(b"first\r\nsec").await.unwrap();
+            client.write_all(b"ond\nlast").await.unwrap();
+        };
+        let read = async move {
+            let mut lines = vec![];
+            while let Some(line) = reader.next().await {
+                lines.push(Vec::from(line.unwrap()));
+            }
+            lines
+        };
+        let (_, lines) = tokio::join!(write, read);
+        assert_eq!(lines, vec![b"first".to_vec(), b"second".to_vec(), b"last".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn test_framed_read_partial_frame_at_eof_is_error() {
+        let (mut client, server) = tokio::io::duplex(64);
+        client.write_all(b"\0\0\0\x09short").await.unwrap();
+        drop(client);
+        let mut reader = WaterFramedRead::new(server, LengthDelimitedCodec::new());
+        let error = reader.next().await.unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "bytes remaining on stream");
+        assert!(reader.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_framed_read_decoder_error_ends_stream() {
+        let (mut client, server) = tokio::io::duplex(64);
+        client.write_all(b"\x7f\0\0\0").await.unwrap();
+        let mut reader = WaterFramedRead::new(
+            server,
+            LengthDelimitedCodec::new().max_frame_length(1024),
+        );
+        let error = reader.next().await.unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(reader.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_framed_backpressure_flushes() {
+        let (client, server) = tokio::io::duplex(1024 * 1024);
+        let mut framed = WaterFramed::new(client, LineDecoder::default());
+        framed.set_backpressure_boundary(16);
+        framed.feed("0123456789").await.unwrap();
+        assert_eq!(framed.write_buffer().len(), 11);
+        // crossing the boundary forces a flush before the next item is accepted
+        framed.feed("abcdefghij").await.unwrap();
+        framed.feed("x").await.unwrap();
+        assert_eq!(framed.write_buffer(), &WaterBuffer::from("x\n"));
+        SinkExt::<&str>::close(&mut framed).await.unwrap();
+
+        let mut reader = WaterFramed::new(server, LineDecoder::default());
+        let mut lines = vec![];
+        while let Some(line) = reader.next().await {
+            lines.push(line.unwrap());
+        }
+        assert_eq!(lines, ["0123456789", "abcdefghij", "x"]);
+    }
+}
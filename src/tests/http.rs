@@ -0,0 +1,159 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::http::*;
+    use super::super::super::*;
+
+    fn buffer_of(data: &[u8]) -> WaterBuffer<u8> {
+        let mut buffer = WaterBuffer::with_capacity(0);
+        buffer.extend_from_slice(data);
+        buffer
+    }
+
+    const REQUEST: &[u8] = b"GET /index.html?q=1 HTTP/1.1\r\nHost: example.com\r\nAccept:  */*  \r\nX-Empty:\r\n\r\n";
+
+    // ============================================================================
+    // REQUEST TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_parse_request() {
+        let mut buffer = buffer_of(REQUEST);
+        buffer.extend_from_slice(b"body");
+        let mut parser = HeadParser::new();
+        let head = parser.parse_request(&mut buffer).unwrap().complete().unwrap();
+        assert_eq!(head.method(), b"GET");
+        assert_eq!(head.target(), b"/index.html?q=1");
+        assert_eq!(head.version(), Version::Http11);
+        assert_eq!(head.head_len(), REQUEST.len());
+        assert_eq!(head.header(b"host"), Some(&b"example.com"[..]));
+        assert_eq!(head.header(b"ACCEPT"), Some(&b"*/*"[..]));
+        assert_eq!(head.header(b"x-empty"), Some(&b""[..]));
+        assert_eq!(head.header(b"missing"), None);
+        let names: Vec<&[u8]> = head.headers().map(|(name, _)| name).collect();
+        assert_eq!(names, [&b"Host"[..], b"Accept", b"X-Empty"]);
+        assert_eq!(head.line().method, 0..3);
+        assert_eq!(head.header_spans()[0].value, 36..47);
+        drop(head);
+        // the head is consumed, the body is left at the front
+        assert_eq!(&buffer[..], b"body");
+    }
+
+    #[test]
+    fn test_parse_request_byte_by_byte() {
+        let mut parser = HeadParser::new();
+        let mut buffer = WaterBuffer::with_capacity(0);
+        for (i, byte) in REQUEST.iter().enumerate() {
+            buffer.push(*byte);
+            let status = parser.parse_request(&mut buffer).unwrap();
+            if i + 1 < REQUEST.len() {
+                assert!(status.is_partial(), "complete after {} bytes", i + 1);
+            } else {
+                assert_eq!(status.complete().unwrap().header(b"Host"), Some(&b"example.com"[..]));
+            }
+        }
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_pipelined_requests_and_leading_empty_lines() {
+        let mut buffer = buffer_of(b"\r\nGET /a HTTP/1.1\r\n\r\nPOST /b HTTP/1.0\nContent-Length: 0\n\n");
+        let mut parser = HeadParser::new();
+        {
+            let head = parser.parse_request(&mut buffer).unwrap().complete().unwrap();
+            assert_eq!(head.target(), b"/a");
+            assert_eq!(head.header_spans().len(), 0);
+        }
+        {
+            let head = parser.parse_request(&mut buffer).unwrap().complete().unwrap();
+            assert_eq!(head.method(), b"POST");
+            assert_eq!(head.version(), Version::Http10);
+            assert_eq!(head.header(b"content-length"), Some(&b"0"[..]));
+        }
+        assert!(buffer.is_empty());
+        assert!(parser.parse_request(&mut buffer).unwrap().is_partial());
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let cases: [(&[u8], HeadError); 7] = [
+            (b"GET /\r\n\r\n", HeadError::InvalidRequestLine),
+            (b"G(T / HTTP/1.1\r\n\r\n", HeadError::InvalidRequestLine),
+            (b"GET  HTTP/1.1\r\n\r\n", HeadError::InvalidRequestLine),
+            (b"GET / HTTP/2.0\r\n\r\n", HeadError::InvalidVersion),
+            (b"GET / HTTP/1.1\r\nBad Name: x\r\n\r\n", HeadError::InvalidHeader),
+            (b"GET / HTTP/1.1\r\nA: b\r\n folded\r\n\r\n", HeadError::InvalidHeader),
+            (b"GET / HTTP/1.1\r\nNoColon\r\n\r\n", HeadError::InvalidHeader),
+        ];
+        for (input, expected) in cases {
+            let mut buffer = buffer_of(input);
+            let error = HeadParser::new().parse_request(&mut buffer).unwrap_err();
+            assert_eq!(error, expected, "{:?}", Escaped::new(input));
+            assert_eq!(buffer.len(), input.len());
+        }
+    }
+
+    // ============================================================================
+    // LIMIT TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_max_headers() {
+        let mut parser = HeadParser::new().max_headers(2);
+        let mut buffer = buffer_of(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\n");
+        assert!(parser.parse_request(&mut buffer).unwrap().is_complete());
+        // detected before the head is complete
+        let mut buffer = buffer_of(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n");
+        let error = parser.parse_request(&mut buffer).unwrap_err();
+        assert_eq!(error, HeadError::TooManyHeaders { max: 2 });
+    }
+
+    #[test]
+    fn test_max_head_size() {
+        let mut parser = HeadParser::new().max_head_size(32);
+        let mut buffer = buffer_of(b"GET / HTTP/1.1\r\nA: 1\r\n\r\n");
+        assert!(parser.parse_request(&mut buffer).unwrap().is_complete());
+        let mut buffer = buffer_of(b"GET /");
+        buffer.extend_from_slice(&[b'a'; 40]);
+        let error = parser.parse_request(&mut buffer).unwrap_err();
+        assert_eq!(error, HeadError::TooLarge { max: 32 });
+        let mut buffer = buffer_of(b"GET / HTTP/1.1\r\nX-Long: aaaaaaaaaaaaaaaa\r\n\r\n");
+        assert_eq!(parser.parse_request(&mut buffer).unwrap_err(), HeadError::TooLarge { max: 32 });
+    }
+
+    // ============================================================================
+    // RESPONSE TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_parse_response() {
+        let mut buffer = buffer_of(b"HTTP/1.1 404 Not Found\r\nContent-Length: 3\r\n\r\nnop");
+        let mut parser = HeadParser::new();
+        {
+            let head = parser.parse_response(&mut buffer).unwrap().complete().unwrap();
+            assert_eq!(head.status(), 404);
+            assert_eq!(head.reason(), b"Not Found");
+            assert_eq!(head.version(), Version::Http11);
+            assert_eq!(head.header(b"Content-Length"), Some(&b"3"[..]));
+        }
+        assert_eq!(&buffer[..], b"nop");
+
+        let mut buffer = buffer_of(b"HTTP/1.0 204\r\n\r\n");
+        let head = parser.parse_response(&mut buffer).unwrap().complete().unwrap();
+        assert_eq!(head.status(), 204);
+        assert_eq!(head.reason(), b"");
+    }
+
+    #[test]
+    fn test_invalid_responses() {
+        let cases: [(&[u8], HeadError); 4] = [
+            (b"HTTP/1.1 20 OK\r\n\r\n", HeadError::InvalidStatusLine),
+            (b"HTTP/1.1 2000 OK\r\n\r\n", HeadError::InvalidStatusLine),
+            (b"HTTP/3 200 OK\r\n\r\n", HeadError::InvalidVersion),
+            (b"HTTP/1.1\r\n\r\n", HeadError::InvalidStatusLine),
+        ];
+        for (input, expected) in cases {
+            let mut buffer = buffer_of(input);
+            assert_eq!(HeadParser::new().parse_response(&mut buffer).unwrap_err(), expected);
+        }
+    }
+}
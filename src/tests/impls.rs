@@ -0,0 +1,272 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<H: Hash + ?Sized>(value: &H) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // ============================================================================
+    // CLONE / DEFAULT TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_clone_is_deep() {
+        let mut a = WaterBuffer::with_capacity(16);
+        a.extend_from_slice(b"hello world");
+        a.advance(6);
+        let mut b = a.clone();
+        assert_eq!(&b[..], b"world");
+        b[0] = b'W';
+        assert_eq!(&a[..], b"world");
+        assert_eq!(&b[..], b"World");
+    }
+
+    #[test]
+    fn test_default_is_empty() {
+        let mut b = WaterBuffer::default();
+        assert!(b.is_empty());
+        assert_eq!(b.cap(), 0);
+        b.extend_from_slice(b"abc");
+        assert_eq!(b, "abc");
+    }
+
+    // ============================================================================
+    // COMPARISON TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_partial_eq_variants() {
+        let b = WaterBuffer::from("GET /");
+        assert_eq!(b, "GET /");
+        assert_eq!(b, *"GET /");
+        assert_eq!(b, b"GET /");
+        assert_eq!(b, &b"GET /"[..]);
+        assert_eq!(b, b"GET /".to_vec());
+        assert_eq!(b"GET /".to_vec(), b);
+        assert_eq!("GET /", b);
+        assert_ne!(b, "GET");
+        assert_ne!(b, "GET /x");
+    }
+
+    #[test]
+    fn test_eq_ignores_capacity_and_start_pos() {
+        let mut a = WaterBuffer::with_capacity(64);
+        a.extend_from_slice(b"xxpayload");
+        a.advance(2);
+        let b = WaterBuffer::from("payload");
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+
+    #[test]
+    fn test_hash_matches_slice() {
+        let b = WaterBuffer::from("some bytes");
+        assert_eq!(hash_of(&b), hash_of(&b"some bytes"[..]));
+    }
+
+    #[test]
+    fn test_ord() {
+        let a = WaterBuffer::from("abc");
+        let b = WaterBuffer::from("abd");
+        let c = WaterBuffer::from("ab");
+        assert!(a < b);
+        assert!(c < a);
+        assert_eq!(a.cmp(&a.clone()), std::cmp::Ordering::Equal);
+    }
+
+    // ============================================================================
+    // CONVERSION TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_from_vec_adopts_allocation() {
+        let mut v = Vec::with_capacity(32);
+        v.extend_from_slice(b"adopted");
+        let ptr = v.as_ptr();
+        let b = WaterBuffer::from(v);
+        assert_eq!(b.pointer as *const u8, ptr);
+        assert_eq!(b.cap(), 32);
+        assert_eq!(b, "adopted");
+    }
+
+    #[test]
+    fn test_into_vec_compacts() {
+        let mut b = WaterBuffer::with_capacity(32);
+        b.extend_from_slice(b"header:body");
+        b.advance(7);
+        let ptr = b.pointer;
+        let v: Vec<u8> = b.into();
+        assert_eq!(v, b"body");
+        assert_eq!(v.as_ptr(), ptr as *const u8);
+        assert_eq!(v.capacity(), 32);
+    }
+
+    #[test]
+    fn test_into_vec_empty() {
+        let v: Vec<u8> = WaterBuffer::with_capacity(0).into();
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn test_from_vec_then_into_vec_round_trip() {
+        let v = b"round trip".to_vec();
+        let mut b = WaterBuffer::from_vec(v);
+        b.extend_from_slice(b"!");
+        b.advance(6);
+        let v = b.into_vec();
+        assert_eq!(v, b"trip!");
+    }
+
+    #[test]
+    fn test_into_boxed_slice() {
+        let mut b = WaterBuffer::with_capacity(8);
+        b.extend_from_slice(b"12345678");
+        let ptr = b.pointer;
+        let boxed = b.into_boxed_slice();
+        assert_eq!(&*boxed, b"12345678");
+        // full buffer needs no reallocation
+        assert_eq!(boxed.as_ptr(), ptr as *const u8);
+
+        let b = WaterBuffer::from(boxed);
+        assert_eq!(b, "12345678");
+    }
+
+    #[test]
+    fn test_raw_parts_round_trip() {
+        let mut b = WaterBuffer::with_capacity(16);
+        b.extend_from_slice(b"..raw");
+        b.advance(2);
+        let (ptr, len, cap) = b.into_raw_parts();
+        assert_eq!((len, cap), (3, 16));
+        let b = unsafe { WaterBuffer::from_raw_parts(ptr, len, cap) };
+        assert_eq!(b, "raw");
+        assert_eq!(b.start_pos, 0);
+    }
+
+    #[test]
+    fn test_from_iterator_and_extend() {
+        let mut b: WaterBuffer<u8> = (b'a'..=b'e').collect();
+        assert_eq!(b, "abcde");
+        b.extend(b"fg".iter());
+        b.extend([b'h', b'i']);
+        assert_eq!(b, "abcdefghi");
+    }
+}
+
+#[cfg(all(test, feature = "circular_buffer"))]
+mod circular_tests {
+    use super::super::super::*;
+    use std::cmp::Ordering;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<H: Hash + ?Sized>(value: &H) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// hasher recording every `write` call, so the block layout fed to it is visible
+    #[derive(Default)]
+    struct Recorder(Vec<Vec<u8>>);
+
+    impl Hasher for Recorder {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.push(bytes.to_vec());
+        }
+    }
+
+    fn writes_of(buffer: &WaterBuffer<u8>) -> Vec<Vec<u8>> {
+        let mut recorder = Recorder::default();
+        buffer.hash(&mut recorder);
+        recorder.0
+    }
+
+    /// a full buffer of `cap` bytes holding the last `cap` bytes of `data`
+    fn wrapped(cap: usize, data: &[u8]) -> WaterBuffer<u8> {
+        let mut buffer = WaterBuffer::with_capacity(cap);
+        buffer.extend_from_slice(data);
+        buffer
+    }
+
+    // ============================================================================
+    // WRAPPED LAYOUT TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_wrapped_layout() {
+        let buffer = wrapped(8, b"abcdefghijk");
+        assert_eq!(buffer.as_slices(), (&b"defgh"[..], &b"ijk"[..]));
+        assert_eq!(buffer, "defghijk");
+    }
+
+    #[test]
+    fn test_eq_across_wrap_points() {
+        let linear = wrapped(8, b"defghijk");
+        assert!(linear.as_slices().1.is_empty());
+        let a = wrapped(8, b"abcdefghijk");
+        let b = wrapped(8, b"xxxxxdefghijk");
+        assert_ne!(a.as_slices(), b.as_slices());
+        assert_eq!(a, b);
+        assert_eq!(a, linear);
+        assert_eq!(linear, a);
+        assert_ne!(a, wrapped(8, b"abcdefghijz"));
+    }
+
+    #[test]
+    fn test_ord_across_wrap_points() {
+        let a = wrapped(8, b"abcdefghijk");
+        let b = wrapped(8, b"xxxxxdefghijk");
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        // differs only after the wrap point
+        let greater = wrapped(8, b"abcdefghijz");
+        assert_eq!(a.cmp(&greater), Ordering::Less);
+        assert_eq!(greater.cmp(&a), Ordering::Greater);
+        assert_eq!(a.cmp(&wrapped(8, b"defghijk")), Ordering::Equal);
+        assert_eq!(a.cmp(&wrapped(8, b"defghijj")), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_hash_across_wrap_points() {
+        let a = wrapped(8, b"abcdefghijk");
+        let b = wrapped(8, b"xxxxxdefghijk");
+        let linear = wrapped(8, b"defghijk");
+        assert_eq!(hash_of(&a), hash_of(&b));
+        assert_eq!(hash_of(&a), hash_of(&linear));
+        assert_ne!(hash_of(&a), hash_of(&wrapped(8, b"abcdefghijz")));
+    }
+
+    #[test]
+    fn test_hash_blocks_ignore_wrap_point() {
+        let data: Vec<u8> = (0..2000u32).map(|i| (i * 7) as u8).collect();
+        let cap = 600;
+        let linear = wrapped(cap, &data[data.len() - cap..]);
+        let expected = writes_of(&linear);
+        // every write but the length prefix is a full block except the last
+        assert_eq!(expected.len(), 1 + cap.div_ceil(256));
+        for skip in [1, 100, 255, 256, 257, 599] {
+            let buffer = wrapped(cap, &data[data.len() - cap - skip..]);
+            assert!(!buffer.as_slices().1.is_empty());
+            assert_eq!(writes_of(&buffer), expected, "skip {skip}");
+        }
+    }
+
+    #[test]
+    fn test_clone_of_wrapped_is_linear() {
+        let a = wrapped(8, b"abcdefghijk");
+        let b = a.clone();
+        assert_eq!(b.as_slices(), (&b"defghijk"[..], &b""[..]));
+        assert_eq!(a, b);
+        assert_eq!(b.cap(), 8);
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::codec::*;
+    use super::super::super::*;
+
+    // ============================================================================
+    // DECODE TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_decode_default() {
+        let mut codec = LengthDelimitedCodec::new();
+        let mut b = WaterBuffer::with_capacity(32);
+        b.extend_from_slice(b"\0\0\0\x05hello\0\0\0\x02hi");
+        assert_eq!(&*codec.decode(&mut b).unwrap().unwrap(), b"hello");
+        assert_eq!(&*codec.decode(&mut b).unwrap().unwrap(), b"hi");
+        assert!(codec.decode(&mut b).unwrap().is_none());
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn test_decode_partial_across_reads() {
+        let mut codec = LengthDelimitedCodec::new().length_field_length(2);
+        let mut b = WaterBuffer::with_capacity(1);
+        b.push(0x00);
+        assert!(codec.decode(&mut b).unwrap().is_none());
+        b.push(0x06);
+        assert!(codec.decode(&mut b).unwrap().is_none());
+        // the rest of the frame has been reserved
+        assert!(b.mut_len() >= 6);
+        b.extend_from_slice(b"wat");
+        assert!(codec.decode(&mut b).unwrap().is_none());
+        b.extend_from_slice(b"er!");
+        let frame = codec.decode(&mut b).unwrap().unwrap();
+        assert_eq!(&*frame, b"water!");
+        assert_eq!(frame.raw(), b"\x00\x06water!");
+        drop(frame);
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn test_decode_frame_not_consumed_until_dropped() {
+        let mut codec = LengthDelimitedCodec::new().length_field_length(1);
+        let mut b = WaterBuffer::from(&b"\x01a\x01b"[..]);
+        let first = codec.decode(&mut b).unwrap().unwrap();
+        assert_eq!(&*first, b"a");
+        assert_eq!(first.raw(), b"\x01a");
+        drop(first);
+        assert_eq!(b, b"\x01b");
+    }
+
+    #[test]
+    fn test_decode_offset_adjustment_and_skip() {
+        // 2 byte type, 2 byte little endian length that counts the whole frame
+        let mut codec = LengthDelimitedCodec::new()
+            .length_field_offset(2)
+            .length_field_length(2)
+            .endian(Endian::Little)
+            .length_adjustment(-4)
+            .num_skip(0);
+        let mut b = WaterBuffer::from(&b"T1\x07\x00abcT2"[..]);
+        assert_eq!(&*codec.decode(&mut b).unwrap().unwrap(), b"T1\x07\x00abc");
+        assert_eq!(b, "T2");
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_before_reserve() {
+        let mut codec = LengthDelimitedCodec::new().max_frame_length(64);
+        let mut b = WaterBuffer::with_capacity(8);
+        b.extend_from_slice(b"\x7f\xff\xff\xff");
+        assert_eq!(
+            codec.decode(&mut b).unwrap_err(),
+            FrameError::TooLarge { len: 0x7fff_ffff + 4, max: 64 }
+        );
+        assert_eq!(b.cap(), 8);
+    }
+
+    #[test]
+    fn test_decode_negative_length() {
+        let mut codec = LengthDelimitedCodec::new().length_field_length(1).length_adjustment(-4);
+        let mut b = WaterBuffer::from(&b"\x02ab"[..]);
+        assert_eq!(codec.decode(&mut b).unwrap_err(), FrameError::InvalidLength);
+    }
+
+    // ============================================================================
+    // ENCODE TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_encode_round_trip() {
+        let mut codec = LengthDelimitedCodec::new().length_field_length(3);
+        let mut b = WaterBuffer::with_capacity(0);
+        codec.encode(b"first", &mut b).unwrap();
+        codec.encode(b"", &mut b).unwrap();
+        assert_eq!(&b[..8], b"\0\0\x05first");
+        assert_eq!(&*codec.decode(&mut b).unwrap().unwrap(), b"first");
+        assert_eq!(&*codec.decode(&mut b).unwrap().unwrap(), b"");
+    }
+
+    #[test]
+    fn test_encode_limits() {
+        let codec = LengthDelimitedCodec::new().length_field_length(1).max_frame_length(1024);
+        let mut b = WaterBuffer::with_capacity(0);
+        assert_eq!(codec.encode(&[0; 256], &mut b), Err(FrameError::InvalidLength));
+        assert_eq!(
+            codec.encode(&[0; 2000], &mut b),
+            Err(FrameError::TooLarge { len: 2001, max: 1024 })
+        );
+        assert!(b.is_empty());
+    }
+}
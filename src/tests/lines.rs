@@ -0,0 +1,125 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::codec::*;
+    use super::super::super::*;
+
+    fn line(decoder: &mut LineDecoder, b: &mut WaterBuffer<u8>) -> Option<WaterBuffer<u8>> {
+        WaterDecoder::decode(decoder, b).unwrap()
+    }
+
+    // ============================================================================
+    // DELIMITER TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_lf_strips_optional_cr() {
+        let mut d = LineDecoder::new(Delimiter::Lf);
+        let mut b = WaterBuffer::from("EHLO a\r\nQUIT\n\n");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "EHLO a");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "QUIT");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "");
+        assert!(line(&mut d, &mut b).is_none());
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn test_crlf_is_strict() {
+        let mut d = LineDecoder::new(Delimiter::CrLf);
+        let mut b = WaterBuffer::from("a\nb\r\n");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "a\nb");
+    }
+
+    #[test]
+    fn test_byte_and_sequence_delimiters() {
+        let mut d = LineDecoder::new(Delimiter::Byte(0));
+        let mut b = WaterBuffer::from(&b"one\0two\0"[..]);
+        assert_eq!(line(&mut d, &mut b).unwrap(), "one");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "two");
+
+        let mut d = LineDecoder::new(Delimiter::Bytes(b"--".to_vec()));
+        let mut b = WaterBuffer::from("x-y--z");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "x-y");
+        assert!(line(&mut d, &mut b).is_none());
+        assert_eq!(b, "z");
+    }
+
+    #[test]
+    fn test_zero_copy_frame_consumes_on_drop() {
+        let mut d = LineDecoder::default();
+        let mut b = WaterBuffer::from("PING\r\nrest");
+        {
+            let frame = d.decode(&mut b).unwrap().unwrap();
+            assert_eq!(&*frame, b"PING");
+            assert_eq!(frame.raw(), b"PING\r\n");
+        }
+        assert_eq!(b, "rest");
+    }
+
+    // ============================================================================
+    // INCREMENTAL TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_partial_lines_across_reads() {
+        let mut d = LineDecoder::new(Delimiter::CrLf);
+        let mut b = WaterBuffer::with_capacity(4);
+        b.extend_from_slice(b"SET k");
+        assert!(line(&mut d, &mut b).is_none());
+        b.extend_from_slice(b" v\r");
+        assert!(line(&mut d, &mut b).is_none());
+        b.extend_from_slice(b"\nGET");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "SET k v");
+        assert!(line(&mut d, &mut b).is_none());
+        b.extend_from_slice(b" k\r\n");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "GET k");
+    }
+
+    #[test]
+    fn test_split_sequence_delimiter() {
+        let mut d = LineDecoder::new(Delimiter::Bytes(b"<end>".to_vec()));
+        let mut b = WaterBuffer::from("body<e");
+        assert!(line(&mut d, &mut b).is_none());
+        b.extend_from_slice(b"nd>");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "body");
+    }
+
+    #[test]
+    fn test_decode_eof_returns_rest() {
+        let mut d = LineDecoder::default();
+        let mut b = WaterBuffer::from("a\nlast");
+        assert_eq!(&*d.decode_eof(&mut b).unwrap().unwrap(), b"a");
+        assert_eq!(&*d.decode_eof(&mut b).unwrap().unwrap(), b"last");
+        assert!(d.decode_eof(&mut b).unwrap().is_none());
+    }
+
+    // ============================================================================
+    // MAX LENGTH TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_max_length_without_delimiter_then_recovers() {
+        let mut d = LineDecoder::new(Delimiter::Lf).max_length(4);
+        let mut b = WaterBuffer::from("toolong");
+        assert_eq!(d.decode(&mut b).unwrap_err(), LineError::TooLong { max: 4 });
+        b.extend_from_slice(b"still\nok\n");
+        assert_eq!(line(&mut d, &mut b).unwrap(), "ok");
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn test_max_length_with_delimiter() {
+        let mut d = LineDecoder::new(Delimiter::Lf).max_length(4);
+        let mut b = WaterBuffer::from("12345\nabcd\r\n");
+        assert_eq!(d.decode(&mut b).unwrap_err(), LineError::TooLong { max: 4 });
+        assert_eq!(line(&mut d, &mut b).unwrap(), "abcd");
+    }
+
+    #[test]
+    fn test_max_length_allows_pending_cr() {
+        let mut d = LineDecoder::new(Delimiter::Lf).max_length(4);
+        let mut b = WaterBuffer::from("abcd\r");
+        assert!(line(&mut d, &mut b).is_none());
+        b.push(b'\n');
+        assert_eq!(line(&mut d, &mut b).unwrap(), "abcd");
+    }
+}
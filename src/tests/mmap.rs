@@ -0,0 +1,158 @@
+#[cfg(all(test, unix, feature = "mmap"))]
+mod tests {
+    use super::super::super::*;
+
+    /// writes `data` to a fresh file in the temp directory
+    fn temp_file(name: &str, data: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("water_buffer_{}_{}", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    // ============================================================================
+    // FILE MAPPING TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_file_mmap_read_and_advance() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let path = temp_file("read", &data);
+        let mut map = unsafe { WaterBuffer::from_file_mmap(&path) }.unwrap();
+        map.advise_sequential().unwrap();
+        assert_eq!(map.len(), data.len());
+        assert_eq!(&map[..], &data[..]);
+        map.advance(9_000);
+        assert_eq!(&map[..], &data[9_000..]);
+        assert_eq!(map.mapped_len(), 10_000);
+        drop(map);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_file_mmap_empty_and_missing() {
+        let path = temp_file("empty", b"");
+        let map = unsafe { WaterBuffer::from_file_mmap(&path) }.unwrap();
+        assert!(map.is_empty());
+        std::fs::remove_file(&path).unwrap();
+        assert!(unsafe { WaterBuffer::from_file_mmap(&path) }.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient space to advance")]
+    fn test_file_mmap_advance_past_end() {
+        let path = temp_file("advance", b"abc");
+        let mut map = unsafe { WaterBuffer::from_file_mmap(&path) }.unwrap();
+        std::fs::remove_file(path).unwrap();
+        map.advance(4);
+    }
+
+    // ============================================================================
+    // ANONYMOUS MAPPING TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_anonymous_mmap_grows_and_keeps_data() {
+        let mut buffer = WaterBuffer::with_anonymous_mmap(10);
+        assert_eq!(buffer.page_mode(), PageMode::Mapped);
+        let first_cap = buffer.cap();
+        assert!(first_cap >= 10);
+        let chunk = [7u8; 1000];
+        for _ in 0..100 {
+            buffer.extend_from_slice(&chunk);
+        }
+        assert_eq!(buffer.len(), 100_000);
+        assert!(buffer.cap() >= 100_000);
+        assert_eq!(buffer.cap() % first_cap, 0);
+        assert_eq!(buffer.page_mode(), PageMode::Mapped);
+        assert!(buffer.iter().all(|&b| b == 7));
+    }
+
+    #[test]
+    fn test_anonymous_mmap_advance_and_reuse() {
+        let mut buffer = WaterBuffer::with_anonymous_mmap(0);
+        buffer.extend_from_slice(b"hello world");
+        buffer.advance(6);
+        assert_eq!(buffer, "world");
+        buffer[0] = b'W';
+        buffer.truncate(3);
+        assert_eq!(buffer, "Wor");
+        buffer.advance(3);
+        assert!(buffer.is_empty());
+        let cap = buffer.cap();
+        buffer.extend_from_slice(&vec![1u8; cap]);
+        assert_eq!(buffer.cap(), cap);
+    }
+
+    #[test]
+    fn test_anonymous_mmap_shrinks_and_hands_over_to_heap() {
+        let mut buffer = WaterBuffer::with_anonymous_mmap(0);
+        let page = buffer.cap();
+        buffer.extend_from_slice(&vec![5u8; page * 3]);
+        buffer.advance(page * 3 - 4);
+        buffer.shrink_to_fit();
+        assert_eq!(buffer.cap(), page);
+        assert_eq!(buffer.page_mode(), PageMode::Mapped);
+        assert_eq!(buffer, [5u8; 4]);
+        assert_eq!(buffer.into_vec(), [5u8; 4]);
+    }
+
+    // ============================================================================
+    // HUGE PAGE TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_water_buffer_huge_pages_fall_back_gracefully() {
+        let mut buffer = WaterBuffer::with_huge_pages(100);
+        if buffer.page_mode() != PageMode::Regular {
+            assert_eq!(buffer.cap(), HUGE_PAGE_SIZE);
+        }
+        buffer.extend_from_slice(b"packet");
+        assert_eq!(buffer, "packet");
+        assert_eq!(WaterBuffer::with_capacity(100).page_mode(), PageMode::Regular);
+    }
+
+    #[test]
+    fn test_water_buffer_huge_pages_grow_and_shrink() {
+        let mut buffer = WaterBuffer::with_huge_pages(HUGE_PAGE_SIZE);
+        let mapped = buffer.page_mode() != PageMode::Regular;
+        let data: Vec<u8> = (0..HUGE_PAGE_SIZE + 10).map(|i| i as u8).collect();
+        buffer.extend_from_slice(&data);
+        assert_eq!(&buffer[..], &data[..]);
+        // growth stays on mapped pages, only hugetlb may fall back to transparent huge pages
+        assert_eq!(buffer.page_mode() != PageMode::Regular, mapped);
+        if mapped {
+            assert_eq!(buffer.cap() % HUGE_PAGE_SIZE, 0);
+        }
+
+        buffer.advance(HUGE_PAGE_SIZE);
+        buffer.shrink_to_fit();
+        assert_eq!(&buffer[..], &data[HUGE_PAGE_SIZE..]);
+        if mapped {
+            assert_eq!(buffer.cap(), HUGE_PAGE_SIZE);
+        }
+
+        buffer.clear();
+        buffer.shrink_to_fit();
+        assert_eq!(buffer.cap(), 0);
+        buffer.extend_from_slice(b"again");
+        assert_eq!(buffer, "again");
+        if mapped {
+            assert_eq!(buffer.cap(), HUGE_PAGE_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_water_buffer_huge_pages_hand_over_to_heap() {
+        let captured = || {
+            let mut buffer = WaterBuffer::with_huge_pages(64);
+            buffer.extend_from_slice(b"..captured");
+            buffer.advance(2);
+            buffer
+        };
+        assert_eq!(captured().into_vec(), b"captured");
+        let (pointer, len, cap) = captured().into_raw_parts();
+        let buffer = unsafe { WaterBuffer::from_raw_parts(pointer, len, cap) };
+        assert_eq!(buffer, "captured");
+        assert_eq!(buffer.page_mode(), PageMode::Regular);
+    }
+}
@@ -1,5 +1,26 @@
 pub (crate) mod not_expand;
+mod chunked;
+mod http;
+mod search;
+mod std_io;
+mod transfer;
+mod mmap;
+mod rope;
+mod chain;
+mod uring;
+mod async_io;
+mod framed;
+mod lines;
+mod length_delimited;
+mod prefix;
+mod varint;
+mod serde;
+mod format;
+mod impls;
+#[cfg(not(feature = "circular_buffer"))]
+mod shrink;
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use super::super::*;
 
@@ -121,6 +142,17 @@ mod tests {
         assert_eq!(&b[..], b"XBCDY");
     }
 
+    #[cfg(not(feature = "circular_buffer"))]
+    #[test]
+    fn test_index_after_advance() {
+        let mut b = WaterBuffer::with_capacity(8);
+        b.extend_from_slice(b"ABCDE");
+        b.advance(2);
+        assert_eq!(b[0], b'C');
+        b[2] = b'Z';
+        assert_eq!(&b[..], b"CDZ");
+    }
+
     #[test]
     fn test_range_indexing() {
         let mut b = WaterBuffer::with_capacity(10);
@@ -534,4 +566,24 @@ mod tests {
         assert_eq!(b.len(), 20);
         assert!(b.cap >= 20);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_range_indexing_after_advance() {
+        let mut b = WaterBuffer::with_capacity(10);
+        b.extend_from_slice(b"0123456789");
+        b.advance(3);
+        assert_eq!(&b[1..4], b"456");
+        assert_eq!(b[4..].len(), 3);
+        assert_eq!(&b[4..], b"789");
+        b[0..2].copy_from_slice(b"AB");
+        assert_eq!(&b[..], b"AB56789");
+    }
+
+    #[test]
+    #[should_panic(expected = "Range out of bounds")]
+    fn test_range_mut_out_of_bounds() {
+        let mut b = WaterBuffer::with_capacity(10);
+        b.extend_from_slice(b"0123");
+        b[2..6].copy_from_slice(b"XYZW");
+    }
+}
@@ -611,7 +611,7 @@ mod comprehensive_tests {
         b.advance(2);
 
         let chunk = b.chunk_mut();
-        assert!(chunk.len() > 0);
+        assert!(!chunk.is_empty());
     }
 
     // ============================================================================
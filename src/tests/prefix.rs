@@ -0,0 +1,149 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::*;
+
+    // ============================================================================
+    // FIXED WIDTH PREFIX TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_u8_prefix() {
+        let mut b = WaterBuffer::with_capacity(16);
+        let p = b.reserve_prefix(PrefixFormat::U8);
+        b.extend_from_slice(b"abc");
+        assert_eq!(b.finish_frame(p), Ok(3));
+        assert_eq!(&b[..], b"\x03abc");
+    }
+
+    #[test]
+    fn test_u16_and_u32_endianness() {
+        let mut b = WaterBuffer::with_capacity(4);
+        let p = b.reserve_prefix(PrefixFormat::U16(Endian::Big));
+        b.extend_from_slice(&[0; 0x102]);
+        b.finish_frame(p).unwrap();
+        assert_eq!(&b[..2], &[0x01, 0x02]);
+
+        let mut b = WaterBuffer::with_capacity(4);
+        let p = b.reserve_prefix(PrefixFormat::U32(Endian::Little));
+        b.extend_from_slice(&[0; 0x102]);
+        b.finish_frame(p).unwrap();
+        assert_eq!(&b[..4], &[0x02, 0x01, 0, 0]);
+    }
+
+    #[test]
+    fn test_prefix_survives_growth_and_compaction() {
+        let mut b = WaterBuffer::with_capacity(8);
+        b.extend_from_slice(b"old");
+        b.advance(3);
+        b.extend_from_slice(b"T");
+        let p = b.reserve_prefix(PrefixFormat::U24(Endian::Big));
+        assert_eq!(p.offset(), 1);
+        b.extend_from_slice(&[7; 1000]);
+        b.finish_frame(p).unwrap();
+        assert_eq!(&b[..4], &[b'T', 0x00, 0x03, 0xe8]);
+        assert_eq!(b.len(), 1004);
+    }
+
+    #[test]
+    fn test_adjusted_length() {
+        // http/2 style: 24-bit length, then type/flags/stream id that are not counted
+        let mut b = WaterBuffer::with_capacity(32);
+        let p = b.reserve_prefix(PrefixFormat::U24(Endian::Big));
+        b.extend_from_slice(&[0x0, 0x1, 0, 0, 0, 1]);
+        b.extend_from_slice(b"data");
+        assert_eq!(b.finish_frame_adjusted(p, -6), Ok(10));
+        assert_eq!(&b[..3], &[0, 0, 4]);
+
+        let mut b = WaterBuffer::with_capacity(32);
+        let p = b.reserve_prefix(PrefixFormat::U8);
+        assert_eq!(b.finish_frame_adjusted(p, -1).map_err(|e| e.error()), Err(PrefixError::Negative));
+    }
+
+    #[test]
+    fn test_too_large() {
+        let mut b = WaterBuffer::with_capacity(512);
+        let p = b.reserve_prefix(PrefixFormat::U8);
+        b.extend_from_slice(&[0; 256]);
+        assert_eq!(
+            b.finish_frame(p).map_err(|e| e.error()),
+            Err(PrefixError::TooLarge { len: 256, max: 255 })
+        );
+    }
+
+    #[test]
+    fn test_cancel_after_failed_finish() {
+        let mut b = WaterBuffer::with_capacity(512);
+        b.extend_from_slice(b"keep");
+        let p = b.reserve_prefix(PrefixFormat::U8);
+        b.extend_from_slice(&[0; 256]);
+        let error = b.finish_frame(p).unwrap_err();
+        assert_eq!(error.to_string(), "frame length 256 does not fit into the prefix (max 255)");
+        b.cancel_frame(error.into_handle());
+        assert_eq!(b, "keep");
+    }
+
+    #[test]
+    fn test_cancel_frame() {
+        let mut b = WaterBuffer::with_capacity(32);
+        b.extend_from_slice(b"keep");
+        let p = b.reserve_prefix(PrefixFormat::U32(Endian::Big));
+        b.extend_from_slice(b"drop me");
+        b.cancel_frame(p);
+        assert_eq!(b, "keep");
+    }
+
+    // ============================================================================
+    // VARINT PREFIX TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_varint_prefix_exact() {
+        let mut b = WaterBuffer::with_capacity(16);
+        let p = b.reserve_prefix(PrefixFormat::Varint(1));
+        b.extend_from_slice(b"hello");
+        b.finish_frame(p).unwrap();
+        assert_eq!(&b[..], b"\x05hello");
+    }
+
+    #[test]
+    fn test_varint_prefix_grows_body() {
+        let mut b = WaterBuffer::with_capacity(16);
+        b.push(0xaa);
+        let p = b.reserve_prefix(PrefixFormat::Varint(1));
+        let body: Vec<u8> = (0..300u32).map(|i| i as u8).collect();
+        b.extend_from_slice(&body);
+        b.finish_frame(p).unwrap();
+        assert_eq!(&b[..3], &[0xaa, 0xac, 0x02]);
+        assert_eq!(&b[3..], &body[..]);
+        b.advance(1);
+        assert_eq!(b.get_uvarint(), Ok(300));
+        assert_eq!(b, body);
+    }
+
+    #[test]
+    fn test_varint_prefix_shrinks_body() {
+        let mut b = WaterBuffer::with_capacity(16);
+        let p = b.reserve_prefix(PrefixFormat::Varint(MAX_VARINT_LEN));
+        b.extend_from_slice(b"xyz");
+        b.finish_frame(p).unwrap();
+        assert_eq!(&b[..], b"\x03xyz");
+    }
+
+    #[test]
+    fn test_varint_fixed_prefix_never_moves() {
+        let mut b = WaterBuffer::with_capacity(16);
+        let p = b.reserve_prefix(PrefixFormat::VarintFixed(2));
+        b.extend_from_slice(b"xyz");
+        b.finish_frame(p).unwrap();
+        assert_eq!(&b[..], b"\x83\x00xyz");
+        assert_eq!(b.get_uvarint(), Ok(3));
+
+        let mut b = WaterBuffer::with_capacity(256);
+        let p = b.reserve_prefix(PrefixFormat::VarintFixed(1));
+        b.extend_from_slice(&[0; 128]);
+        assert_eq!(
+            b.finish_frame(p).map_err(|e| e.error()),
+            Err(PrefixError::TooLarge { len: 128, max: 127 })
+        );
+    }
+}
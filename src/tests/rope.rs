@@ -0,0 +1,99 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::*;
+
+    fn rope_of(size: usize, data: &[u8]) -> WaterRope {
+        let mut rope = WaterRope::with_segment_size(size);
+        rope.extend_from_slice(data);
+        rope
+    }
+
+    // ============================================================================
+    // APPEND / INDEX TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_extend_spills_into_new_segments() {
+        let mut rope = rope_of(4, b"abcdef");
+        assert_eq!(rope.segment_count(), 2);
+        rope.extend_from_slice(b"ghijk");
+        assert_eq!(rope.segment_count(), 3);
+        assert_eq!(rope.len(), 11);
+        let chunks: Vec<&[u8]> = rope.chunks().collect();
+        assert_eq!(chunks, [&b"abcd"[..], b"efgh", b"ijk"]);
+    }
+
+    #[test]
+    fn test_logical_indexing() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut rope = rope_of(7, &data);
+        for i in 0..100 {
+            assert_eq!(rope[i], i as u8);
+        }
+        rope.advance(10);
+        for i in 0..90 {
+            assert_eq!(rope.get(i), Some(i as u8 + 10));
+        }
+        assert_eq!(rope.get(90), None);
+        assert!(rope.iter().copied().eq(10..100u8));
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn test_index_out_of_bounds() {
+        let rope = rope_of(4, b"abc");
+        let _ = rope[3];
+    }
+
+    // ============================================================================
+    // ADVANCE TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_advance_frees_leading_segments() {
+        let mut rope = rope_of(4, b"abcdefghij");
+        rope.advance(5);
+        assert_eq!(rope.segment_count(), 2);
+        assert_eq!(rope.chunks().next().unwrap(), b"fgh");
+        rope.advance(5);
+        assert!(rope.is_empty());
+        assert_eq!(rope.segment_count(), 0);
+        rope.extend_from_slice(b"xy");
+        assert_eq!(rope[1], b'y');
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient space to advance")]
+    fn test_advance_past_end_panics() {
+        let mut rope = rope_of(4, b"abc");
+        rope.advance(4);
+    }
+
+    // ============================================================================
+    // CONTIGUOUS ACCESS TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_make_contiguous() {
+        let mut rope = rope_of(4, b"GET / HTTP/1.1\r\n");
+        rope.advance(1);
+        assert_eq!(rope.make_contiguous(0..2), b"ET");
+        assert_eq!(rope.make_contiguous(2..13), b" / HTTP/1.1");
+        assert_eq!(rope.make_contiguous(5..5), b"");
+        // the rope itself is untouched
+        assert_eq!(rope.len(), 15);
+        assert_eq!(rope.segment_count(), 4);
+
+        let mut out = [0u8; 4];
+        rope.copy_to_slice(11, &mut out);
+        assert_eq!(&out, b".1\r\n");
+    }
+
+    #[test]
+    fn test_extend_with_iterator() {
+        let mut rope = WaterRope::with_segment_size(3);
+        rope.extend(b"hello".iter());
+        rope.extend([b'!']);
+        assert_eq!(rope.make_contiguous(0..6), b"hello!");
+    }
+}
@@ -0,0 +1,150 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::*;
+
+    fn buffer_of(data: &[u8]) -> WaterBuffer<u8> {
+        let mut buffer = WaterBuffer::with_capacity(0);
+        buffer.extend_from_slice(data);
+        buffer
+    }
+
+    /// deterministic pseudo random bytes drawn from a small alphabet so matches are frequent
+    fn noise(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(2654435761).max(1);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                b"abcd"[(state % 4) as usize]
+            })
+            .collect()
+    }
+
+    fn naive_find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        if needle.is_empty() {
+            return Some(0);
+        }
+        haystack.windows(needle.len()).position(|window| window == needle)
+    }
+
+    // ============================================================================
+    // BYTE SEARCH TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_find_byte_and_rfind() {
+        let buffer = buffer_of(b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n");
+        assert_eq!(buffer.find_byte(b' '), Some(3));
+        assert_eq!(buffer.rfind(b' '), Some(31));
+        assert_eq!(buffer.find_byte(b'\r'), Some(24));
+        assert_eq!(buffer.rfind(b'\n'), Some(buffer.len() - 1));
+        assert_eq!(buffer.find_byte(b'#'), None);
+        assert_eq!(buffer.rfind(b'#'), None);
+    }
+
+    #[test]
+    fn test_search_respects_readable_window() {
+        let mut buffer = buffer_of(b"x----------------------------------------x");
+        buffer.advance(1);
+        assert_eq!(buffer.find_byte(b'x'), Some(buffer.len() - 1));
+        buffer.truncate(buffer.len() - 1);
+        assert_eq!(buffer.find_byte(b'x'), None);
+        assert_eq!(buffer.count_byte(b'-'), buffer.len());
+    }
+
+    #[test]
+    fn test_byte_search_matches_scalar_at_every_offset() {
+        for len in [0, 1, 15, 16, 17, 31, 32, 33, 63, 64, 65, 200] {
+            let mut data = vec![b'.'; len];
+            assert_eq!(buffer_of(&data).find_byte(b'!'), None);
+            for pos in 0..len {
+                data[pos] = b'!';
+                let buffer = buffer_of(&data);
+                assert_eq!(buffer.find_byte(b'!'), Some(pos), "len {len} pos {pos}");
+                assert_eq!(buffer.rfind(b'!'), Some(pos), "len {len} pos {pos}");
+                assert_eq!(buffer.count_byte(b'!'), 1);
+                data[pos] = b'.';
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_byte() {
+        let data = noise(1000, 7);
+        let buffer = buffer_of(&data);
+        for byte in *b"abcdz" {
+            assert_eq!(buffer.count_byte(byte), data.iter().filter(|&&b| b == byte).count());
+        }
+    }
+
+    #[test]
+    fn test_find_any_of() {
+        let buffer = buffer_of(b"key=value; other=thing");
+        assert_eq!(buffer.find_any_of(b";="), Some(3));
+        assert_eq!(buffer.find_any_of(b";"), Some(9));
+        assert_eq!(buffer.find_any_of(b""), None);
+        assert_eq!(buffer.find_any_of(b"#!"), None);
+
+        let mut data = vec![b'.'; 100];
+        data[70] = b'9';
+        let buffer = buffer_of(&data);
+        // larger sets use the lookup table
+        assert_eq!(buffer.find_any_of(b"0123456789"), Some(70));
+        assert_eq!(buffer.find_any_of(b"79"), Some(70));
+    }
+
+    // ============================================================================
+    // SUBSLICE TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_find_subslice() {
+        let buffer = buffer_of(b"Content-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello");
+        assert_eq!(buffer.find_subslice(b"\r\n\r\n"), Some(43));
+        assert_eq!(buffer.find_subslice(b"Length"), Some(34));
+        assert_eq!(buffer.find_subslice(b""), Some(0));
+        assert_eq!(buffer.find_subslice(b"hello!"), None);
+        assert_eq!(buffer.find_subslice(b"missing"), None);
+    }
+
+    #[test]
+    fn test_find_subslice_matches_naive() {
+        for seed in 1..40 {
+            let data = noise(300, seed);
+            let buffer = buffer_of(&data);
+            for needle_len in [2, 3, 5, 8, 17, 40] {
+                let start = (seed as usize * 13) % (data.len() - needle_len);
+                let present = &data[start..start + needle_len];
+                assert_eq!(buffer.find_subslice(present), naive_find(&data, present));
+                let absent = noise(needle_len, seed + 1000);
+                assert_eq!(buffer.find_subslice(&absent), naive_find(&data, &absent));
+            }
+        }
+    }
+
+    // ============================================================================
+    // ITERATOR TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_find_iter() {
+        let buffer = buffer_of(b"a\r\nbb\r\n\r\nccc\r\n");
+        let found: Vec<usize> = buffer.find_iter(b"\r\n").collect();
+        assert_eq!(found, [1, 5, 7, 12]);
+        let found: Vec<usize> = buffer.find_iter(b"c").collect();
+        assert_eq!(found, [9, 10, 11]);
+        // matches do not overlap
+        let buffer = buffer_of(b"aaaaa");
+        assert_eq!(buffer.find_iter(b"aa").collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(buffer.find_iter(b"").count(), 6);
+    }
+
+    #[test]
+    fn test_find_iter_long_haystack() {
+        let data = noise(5000, 3);
+        let buffer = buffer_of(&data);
+        let expected = data.iter().enumerate().filter(|(_, b)| **b == b'a').map(|(i, _)| i);
+        assert!(buffer.find_iter(b"a").eq(expected));
+    }
+}
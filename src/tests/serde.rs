@@ -0,0 +1,42 @@
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::super::super::*;
+    use serde_test::{assert_de_tokens, assert_tokens, Token};
+
+    #[test]
+    fn test_serialize_as_bytes() {
+        let b = WaterBuffer::from("water");
+        assert_tokens(&b, &[Token::Bytes(b"water")]);
+    }
+
+    #[test]
+    fn test_serialize_readable_window_only() {
+        let mut b = WaterBuffer::with_capacity(16);
+        b.extend_from_slice(b"--body");
+        b.advance(2);
+        serde_test::assert_ser_tokens(&b, &[Token::Bytes(b"body")]);
+    }
+
+    #[test]
+    fn test_deserialize_owned_and_borrowed() {
+        let expected = WaterBuffer::from("abc");
+        assert_de_tokens(&expected, &[Token::ByteBuf(b"abc")]);
+        assert_de_tokens(&expected, &[Token::BorrowedBytes(b"abc")]);
+        assert_de_tokens(&expected, &[Token::Str("abc")]);
+    }
+
+    #[test]
+    fn test_deserialize_seq_fallback() {
+        let expected = WaterBuffer::from(&[1u8, 2, 255][..]);
+        assert_de_tokens(
+            &expected,
+            &[
+                Token::Seq { len: Some(3) },
+                Token::U8(1),
+                Token::U8(2),
+                Token::U8(255),
+                Token::SeqEnd,
+            ],
+        );
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use super::super::super::*;
+
+    // ============================================================================
+    // SHRINK TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut b = WaterBuffer::with_capacity(1024);
+        b.extend_from_slice(b"hello");
+        b.shrink_to_fit();
+        assert_eq!(b.cap(), 5);
+        assert_eq!(&b[..], b"hello");
+    }
+
+    #[test]
+    fn test_shrink_to_fit_compacts_start_pos() {
+        let mut b = WaterBuffer::with_capacity(64);
+        b.extend_from_slice(b"0123456789");
+        b.advance(6);
+        b.shrink_to_fit();
+        assert_eq!(b.start_pos, 0);
+        assert_eq!(b.cap(), 4);
+        assert_eq!(&b[..], b"6789");
+    }
+
+    #[test]
+    fn test_shrink_to_keeps_min_capacity() {
+        let mut b = WaterBuffer::with_capacity(4096);
+        b.extend_from_slice(b"abc");
+        b.shrink_to(128);
+        assert_eq!(b.cap(), 128);
+        assert_eq!(&b[..], b"abc");
+
+        // never grows
+        b.shrink_to(1024);
+        assert_eq!(b.cap(), 128);
+    }
+
+    #[test]
+    fn test_shrink_to_never_drops_data() {
+        let mut b = WaterBuffer::with_capacity(16);
+        b.extend_from_slice(b"0123456789");
+        b.shrink_to(2);
+        assert_eq!(b.cap(), 10);
+        assert_eq!(&b[..], b"0123456789");
+    }
+
+    #[test]
+    fn test_shrink_empty_then_reuse() {
+        let mut b = WaterBuffer::with_capacity(256);
+        b.shrink_to_fit();
+        assert_eq!(b.cap(), 0);
+        b.extend_from_slice(b"again");
+        assert_eq!(&b[..], b"again");
+        b.push(b'!');
+        assert_eq!(&b[..], b"again!");
+    }
+
+    #[test]
+    fn test_idle_shrink_waits_for_ticks() {
+        let mut b = WaterBuffer::with_capacity(1024 * 1024);
+        let mut idle = IdleShrink::new(ShrinkPolicy::new(1024, 3));
+        assert!(!idle.tick(&mut b));
+        assert!(!idle.tick(&mut b));
+        assert!(idle.tick(&mut b));
+        assert_eq!(b.cap(), 1024);
+        // already below the retained capacity
+        assert!(!idle.tick(&mut b));
+    }
+
+    #[test]
+    fn test_idle_shrink_resets_on_activity() {
+        let mut b = WaterBuffer::with_capacity(4096);
+        let mut idle = IdleShrink::new(ShrinkPolicy::new(64, 2));
+        assert!(!idle.tick(&mut b));
+        b.extend_from_slice(b"busy");
+        assert!(!idle.tick(&mut b));
+        b.clear();
+        assert!(!idle.tick(&mut b));
+        idle.touch();
+        assert!(!idle.tick(&mut b));
+        assert!(idle.tick(&mut b));
+        assert_eq!(b.cap(), 64);
+    }
+}
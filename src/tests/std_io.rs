@@ -0,0 +1,252 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::*;
+    use std::io::{self, Cursor, Read, Write};
+
+    /// hands out `data` in pieces of `step`, failing every other call with `error`
+    struct Flaky {
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+        error: Option<io::ErrorKind>,
+        fail_next: bool,
+    }
+
+    impl Flaky {
+        fn new(data: &[u8], step: usize, error: Option<io::ErrorKind>) -> Flaky {
+            Flaky { data: data.to_vec(), pos: 0, step, error, fail_next: true }
+        }
+    }
+
+    impl Read for Flaky {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if let Some(kind) = self.error {
+                self.fail_next = !self.fail_next;
+                if !self.fail_next {
+                    return Err(kind.into());
+                }
+            }
+            let n = buf.len().min(self.step).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    impl Write for Flaky {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if let Some(kind) = self.error {
+                self.fail_next = !self.fail_next;
+                if !self.fail_next {
+                    return Err(kind.into());
+                }
+            }
+            let n = buf.len().min(self.step);
+            self.data.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // ============================================================================
+    // READ TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_read_from_appends_after_existing_data() {
+        let mut buffer = WaterBuffer::with_capacity(0);
+        buffer.extend_from_slice(b"head ");
+        let n = buffer.read_from(&mut Cursor::new(b"tail".to_vec()), 16).unwrap();
+        assert_eq!(n, 4);
+        assert_eq!(&buffer[..], b"head tail");
+        assert_eq!(buffer.read_from(&mut Cursor::new(Vec::new()), 16).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_read_from_reserves_hint() {
+        let mut buffer = WaterBuffer::with_capacity(4);
+        buffer.extend_from_slice(b"abcd");
+        buffer.read_from(&mut io::repeat(7), 100).unwrap();
+        assert!(buffer.len() >= 104);
+        assert!(buffer[4..].iter().all(|&b| b == 7));
+    }
+
+    #[test]
+    fn test_read_from_retries_interrupted() {
+        let mut reader = Flaky::new(b"data", 16, Some(io::ErrorKind::Interrupted));
+        let mut buffer = WaterBuffer::with_capacity(16);
+        assert_eq!(buffer.read_from(&mut reader, 16).unwrap(), 4);
+        assert_eq!(&buffer[..], b"data");
+    }
+
+    #[test]
+    fn test_read_from_returns_would_block() {
+        let mut reader = Flaky::new(b"data", 16, Some(io::ErrorKind::WouldBlock));
+        let mut buffer = WaterBuffer::with_capacity(16);
+        let error = buffer.read_from(&mut reader, 16).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.read_from(&mut reader, 16).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_read_to_end_from_collects_everything() {
+        let data: Vec<u8> = (0..50_000u32).map(|i| i as u8).collect();
+        let mut buffer = WaterBuffer::with_capacity(0);
+        let n = buffer.read_to_end_from(&mut Flaky::new(&data, 3000, None), data.len()).unwrap();
+        assert_eq!(n, data.len());
+        assert_eq!(&buffer[..], &data[..]);
+    }
+
+    #[test]
+    fn test_read_to_end_from_enforces_limit() {
+        let mut buffer = WaterBuffer::with_capacity(0);
+        let error = buffer.read_to_end_from(&mut Cursor::new(vec![1u8; 101]), 100).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(buffer.len(), 101);
+    }
+
+    #[test]
+    fn test_read_to_end_from_unlimited() {
+        let mut buffer = WaterBuffer::with_capacity(0);
+        let n = buffer.read_to_end_from(&mut Cursor::new(vec![9u8; 20_000]), usize::MAX).unwrap();
+        assert_eq!(n, 20_000);
+        assert!(buffer.iter().all(|&b| b == 9));
+    }
+
+    #[test]
+    fn test_read_from_hands_out_zeroed_spare() {
+        struct ZeroChecking;
+
+        impl Read for ZeroChecking {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                assert!(buf.iter().all(|&b| b == 0), "reader was handed stale bytes");
+                buf[..3].copy_from_slice(b"new");
+                Ok(3)
+            }
+        }
+
+        let mut buffer = WaterBuffer::with_capacity(16);
+        buffer.extend_from_slice(b"stale bytes");
+        buffer.clear();
+        assert_eq!(buffer.read_from(&mut ZeroChecking, 16).unwrap(), 3);
+        assert_eq!(&buffer[..], b"new");
+    }
+
+    #[test]
+    fn test_read_from_zeroes_spare_once() {
+        struct Scribbling(bool);
+
+        impl Read for Scribbling {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if !self.0 {
+                    self.0 = true;
+                    buf.fill(b'x');
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                assert!(buf.iter().all(|&b| b == b'x'), "spare capacity was zeroed twice");
+                Ok(buf.len())
+            }
+        }
+
+        let mut buffer = WaterBuffer::with_capacity(64);
+        assert_eq!(buffer.read_from(&mut Scribbling(false), 64).unwrap(), 64);
+    }
+
+    #[test]
+    fn test_read_to_end_from_resumes_after_would_block() {
+        let mut reader = Flaky::new(b"resumable", 4, Some(io::ErrorKind::WouldBlock));
+        let mut buffer = WaterBuffer::with_capacity(0);
+        let mut blocked = 0;
+        loop {
+            match buffer.read_to_end_from(&mut reader, 64) {
+                Ok(_) => break,
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => blocked += 1,
+                Err(error) => panic!("{error}"),
+            }
+        }
+        assert!(blocked > 0);
+        assert_eq!(&buffer[..], b"resumable");
+    }
+
+    #[test]
+    fn test_read_to_end_from_limit_spans_would_block() {
+        let mut reader = Flaky::new(&[5u8; 1000], 16, Some(io::ErrorKind::WouldBlock));
+        let mut buffer = WaterBuffer::with_capacity(0);
+        let error = loop {
+            match buffer.read_to_end_from(&mut reader, 100) {
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => continue,
+                result => break result.unwrap_err(),
+            }
+        };
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(buffer.len(), 101);
+    }
+
+    #[test]
+    fn test_read_to_end_from_counts_existing_data() {
+        let mut buffer = WaterBuffer::from("head");
+        let n = buffer.read_to_end_from(&mut Cursor::new(b"body".to_vec()), 8).unwrap();
+        assert_eq!(n, 4);
+        let error = buffer.read_to_end_from(&mut Cursor::new(b"!".to_vec()), 8).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    // ============================================================================
+    // WRITE TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_write_to_advances_by_written() {
+        let mut writer = Flaky::new(b"", 3, None);
+        let mut buffer = WaterBuffer::with_capacity(0);
+        buffer.extend_from_slice(b"abcdefg");
+        assert_eq!(buffer.write_to(&mut writer).unwrap(), 3);
+        assert_eq!(&buffer[..], b"defg");
+        assert_eq!(writer.data, b"abc");
+    }
+
+    #[test]
+    fn test_write_to_empty_buffer() {
+        let mut buffer = WaterBuffer::with_capacity(8);
+        let mut out = Vec::new();
+        assert_eq!(buffer.write_to(&mut out).unwrap(), 0);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_write_all_to_handles_interrupted_and_would_block() {
+        let mut buffer = WaterBuffer::with_capacity(0);
+        buffer.extend_from_slice(b"hello world");
+
+        let mut writer = Flaky::new(b"", 4, Some(io::ErrorKind::Interrupted));
+        buffer.write_all_to(&mut writer).unwrap();
+        assert!(buffer.is_empty());
+        assert_eq!(writer.data, b"hello world");
+
+        buffer.extend_from_slice(b"hello world");
+        let mut writer = Flaky::new(b"", 4, Some(io::ErrorKind::WouldBlock));
+        assert_eq!(buffer.write_all_to(&mut writer).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(buffer.len(), 11);
+        loop {
+            match buffer.write_all_to(&mut writer) {
+                Ok(()) => break,
+                Err(error) => assert_eq!(error.kind(), io::ErrorKind::WouldBlock),
+            }
+        }
+        assert_eq!(writer.data, b"hello world");
+    }
+
+    #[test]
+    fn test_write_all_to_write_zero() {
+        let mut buffer = WaterBuffer::with_capacity(0);
+        buffer.extend_from_slice(b"abc");
+        let mut full = [0u8; 0];
+        let error = buffer.write_all_to(&mut &mut full[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(buffer.len(), 3);
+    }
+}
@@ -0,0 +1,142 @@
+#[cfg(all(test, target_os = "linux", feature = "zero-copy"))]
+mod tests {
+    use super::super::super::transfer::*;
+    use super::super::super::*;
+    use std::io::{Read, Seek, SeekFrom, Write};
+    use std::os::unix::net::UnixStream;
+
+    /// creates a file in the temp directory holding `data`, opened for reading
+    fn temp_file(name: &str, data: &[u8]) -> (std::path::PathBuf, std::fs::File) {
+        let path = std::env::temp_dir().join(format!("water_transfer_{}_{}", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        (path, file)
+    }
+
+    fn body() -> Vec<u8> {
+        (0..200_000u32).map(|i| (i % 253) as u8).collect()
+    }
+
+    // ============================================================================
+    // ZERO-COPY PATHS
+    // ============================================================================
+
+    #[test]
+    fn test_send_with_header_over_socket() {
+        let data = body();
+        let (path, file) = temp_file("sendfile", &data);
+        let (ours, mut theirs) = UnixStream::pair().unwrap();
+        let expected_len = data.len();
+        let reader = std::thread::spawn(move || {
+            let mut out = Vec::new();
+            theirs.read_to_end(&mut out).unwrap();
+            out
+        });
+
+        let mut header = WaterBuffer::from("HTTP/1.1 200 OK\r\n\r\n");
+        let mut scratch = WaterBuffer::with_capacity(0);
+        let mut offset = 100;
+        let sent = send_with_header(&ours, &mut header, &file, Some(&mut offset), u64::MAX, &mut scratch).unwrap();
+        drop(ours);
+        assert_eq!(sent.method, TransferMethod::Sendfile);
+        assert_eq!(sent.bytes, 19 + expected_len as u64 - 100);
+        assert_eq!(offset, expected_len as u64);
+        assert!(header.is_empty());
+
+        let out = reader.join().unwrap();
+        assert_eq!(&out[..19], b"HTTP/1.1 200 OK\r\n\r\n");
+        assert_eq!(&out[19..], &data[100..]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_splice_file_into_pipe() {
+        let (path, file) = temp_file("splice", b"spliced bytes");
+        let (mut reader, writer) = std::io::pipe().unwrap();
+        let mut scratch = WaterBuffer::with_capacity(0);
+        let moved = splice_all(&file, &writer, Some(&mut 8), 5, &mut scratch).unwrap();
+        assert_eq!(moved, Transferred { bytes: 5, method: TransferMethod::Splice });
+        drop(writer);
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        assert_eq!(out, "bytes");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_copy_file_range_between_files() {
+        let data = body();
+        let (from_path, from) = temp_file("copy_from", &data);
+        let (to_path, _) = temp_file("copy_to", b"");
+        let mut to = std::fs::OpenOptions::new().write(true).read(true).open(&to_path).unwrap();
+        let mut scratch = WaterBuffer::with_capacity(0);
+        let copied = copy_file_range_all(&from, &to, None, u64::MAX, &mut scratch).unwrap();
+        assert_eq!(copied.bytes, data.len() as u64);
+        // some filesystems refuse the call, the copy fallback must give the same result
+        assert!(matches!(copied.method, TransferMethod::CopyFileRange | TransferMethod::Copy));
+        let mut out = Vec::new();
+        to.seek(SeekFrom::Start(0)).unwrap();
+        to.read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+        std::fs::remove_file(from_path).unwrap();
+        std::fs::remove_file(to_path).unwrap();
+    }
+
+    // ============================================================================
+    // FALLBACK PATH
+    // ============================================================================
+
+    #[test]
+    fn test_sendfile_from_pipe_falls_back_to_copy() {
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(b"piped input").unwrap();
+        drop(writer);
+        let (ours, mut theirs) = UnixStream::pair().unwrap();
+        let mut scratch = WaterBuffer::with_capacity(4);
+        let moved = sendfile_all(&ours, &reader, None, u64::MAX, &mut scratch).unwrap();
+        assert_eq!(moved, Transferred { bytes: 11, method: TransferMethod::Copy });
+        drop(ours);
+        let mut out = Vec::new();
+        theirs.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"piped input");
+    }
+
+    #[test]
+    fn test_offset_past_off_t_is_rejected() {
+        let (path, file) = temp_file("huge_offset", b"data");
+        let (ours, _theirs) = UnixStream::pair().unwrap();
+        let mut scratch = WaterBuffer::with_capacity(0);
+        let mut offset = u64::MAX;
+        let error = sendfile_all(&ours, &file, Some(&mut offset), 4, &mut scratch).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(offset, u64::MAX);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_bad_descriptor_is_an_error() {
+        let (path, _) = temp_file("write_only", b"data");
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        let (ours, _theirs) = UnixStream::pair().unwrap();
+        let mut scratch = WaterBuffer::with_capacity(0);
+        let error = sendfile_all(&ours, &file, None, 4, &mut scratch).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::EBADF));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_limited_length() {
+        let (path, file) = temp_file("limited", b"0123456789");
+        let (ours, mut theirs) = UnixStream::pair().unwrap();
+        let mut scratch = WaterBuffer::with_capacity(0);
+        let moved = sendfile_all(&ours, &file, None, 4, &mut scratch).unwrap();
+        assert_eq!(moved.bytes, 4);
+        let moved = sendfile_all(&ours, &file, None, 100, &mut scratch).unwrap();
+        assert_eq!(moved.bytes, 6);
+        drop(ours);
+        let mut out = Vec::new();
+        theirs.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"0123456789");
+        std::fs::remove_file(path).unwrap();
+    }
+}
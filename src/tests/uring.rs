@@ -0,0 +1,389 @@
+#[cfg(all(test, feature = "uring"))]
+mod tests {
+    use super::super::super::helper::*;
+    use super::super::super::*;
+    use std::io::{Read, Write};
+    use tokio_uring::net::TcpStream;
+
+    /// connected pair of a tokio-uring stream and a blocking std stream
+    fn pair() -> (std::net::TcpStream, std::net::TcpStream) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (client, server)
+    }
+
+    // ============================================================================
+    // READ HELPERS
+    // ============================================================================
+
+    #[test]
+    fn test_read_more_appends_after_filled_bytes() {
+        let (client, mut server) = pair();
+        server.write_all(b" world").unwrap();
+        tokio_uring::start(async move {
+            let stream = TcpStream::from_std(client);
+            let mut buffer = WaterBuffer::with_capacity(4);
+            buffer.extend_from_slice(b"hello");
+            let (result, buffer) = read_more(&stream, buffer, 16).await;
+            assert_eq!(result.unwrap(), 6);
+            assert_eq!(buffer, b"hello world");
+        });
+    }
+
+    #[test]
+    fn test_read_exact_across_writes() {
+        let (client, mut server) = pair();
+        let writer = std::thread::spawn(move || {
+            for chunk in [&b"ab"[..], b"cdef", b"ghij"] {
+                server.write_all(chunk).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        });
+        tokio_uring::start(async move {
+            let stream = TcpStream::from_std(client);
+            let (result, buffer) = read_exact(&stream, WaterBuffer::with_capacity(2), 8).await;
+            result.unwrap();
+            assert!(buffer.len() >= 8);
+            assert_eq!(&buffer[..8], b"abcdefgh");
+        });
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn test_read_exact_eof() {
+        let (client, mut server) = pair();
+        server.write_all(b"abc").unwrap();
+        drop(server);
+        tokio_uring::start(async move {
+            let stream = TcpStream::from_std(client);
+            let (result, buffer) = read_exact(&stream, WaterBuffer::with_capacity(8), 8).await;
+            assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+            assert_eq!(buffer, b"abc");
+        });
+    }
+
+    // ============================================================================
+    // WRITE HELPERS
+    // ============================================================================
+
+    #[test]
+    fn test_write_all_consumes_buffer() {
+        let (client, mut server) = pair();
+        let payload: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        let expected = payload.clone();
+        let reader = std::thread::spawn(move || {
+            let mut out = Vec::new();
+            server.read_to_end(&mut out).unwrap();
+            out
+        });
+        tokio_uring::start(async move {
+            let stream = TcpStream::from_std(client);
+            let mut buffer = WaterBuffer::from_vec(payload);
+            buffer.advance(10);
+            let (result, buffer) = write_all(&stream, buffer).await;
+            result.unwrap();
+            assert!(buffer.is_empty());
+        });
+        assert_eq!(reader.join().unwrap(), &expected[10..]);
+    }
+
+    // ============================================================================
+    // READ / WRITE SLICES
+    // ============================================================================
+
+    #[test]
+    fn test_io_buf_offsets_are_consistent() {
+        use tokio_uring::buf::{IoBuf, IoBufMut};
+
+        let mut buffer = WaterBuffer::with_capacity(16);
+        buffer.extend_from_slice(b"xxabcd");
+        buffer.advance(2);
+        assert_eq!(buffer.bytes_init(), 4);
+        assert_eq!(buffer.bytes_total(), 14);
+        unsafe { buffer.set_init(2) };
+        assert_eq!(buffer, b"abcd");
+
+        let mut slice = buffer.into_read_slice(0);
+        assert_eq!(slice.bytes_init(), 0);
+        assert_eq!(slice.bytes_total(), 10);
+        assert_eq!(slice.stable_ptr(), unsafe { slice.buffer().as_ptr().add(4) });
+        unsafe {
+            slice.stable_mut_ptr().copy_from_nonoverlapping(b"ef".as_ptr(), 2);
+            slice.set_init(2);
+            slice.set_init(1);
+        }
+        let buffer = slice.into_inner();
+        assert_eq!(buffer, b"abcdef");
+
+        let slice = buffer.into_write_slice();
+        assert_eq!(slice.bytes_init(), 6);
+        assert_eq!(slice.bytes_total(), 6);
+        assert_eq!(slice.into_inner(), b"abcdef");
+    }
+
+    #[test]
+    fn test_read_slice_over_socketpair() {
+        let (ours, mut theirs) = std::os::unix::net::UnixStream::pair().unwrap();
+        theirs.write_all(&[1u8; 64]).unwrap();
+        tokio_uring::start(async move {
+            let stream = tokio_uring::net::UnixStream::from_std(ours);
+            let mut buffer = WaterBuffer::with_capacity(32);
+            buffer.extend_from_slice(&[0u8; 24]);
+            buffer.advance(20);
+            // only the spare capacity may be filled, the rest of the data stays queued
+            let (result, slice) = stream.read(buffer.into_read_slice(0)).await;
+            let buffer = slice.into_inner();
+            let n = result.unwrap();
+            assert_eq!(n, 8);
+            assert_eq!(buffer.len(), 12);
+            assert_eq!(&buffer[..4], &[0u8; 4]);
+            assert!(buffer[4..].iter().all(|&b| b == 1));
+
+            let (result, slice) = stream.write(buffer.into_write_slice()).submit().await;
+            let mut buffer = slice.into_inner();
+            buffer.advance(result.unwrap());
+            assert!(buffer.is_empty());
+        });
+        let mut echoed = [9u8; 12];
+        theirs.read_exact(&mut echoed).unwrap();
+        assert_eq!(&echoed[..4], &[0u8; 4]);
+        assert!(echoed[4..].iter().all(|&b| b == 1));
+    }
+
+    #[test]
+    fn test_read_slice_over_pipe() {
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(b"through the pipe").unwrap();
+        drop(writer);
+        tokio_uring::start(async move {
+            let file = tokio_uring::fs::File::from_std(std::fs::File::from(std::os::fd::OwnedFd::from(reader)));
+            let mut buffer = WaterBuffer::with_capacity(4);
+            buffer.extend_from_slice(b"> ");
+            loop {
+                let (result, slice) = file.read_at(buffer.into_read_slice(4), 0).await;
+                buffer = slice.into_inner();
+                if result.unwrap() == 0 {
+                    break;
+                }
+            }
+            assert_eq!(buffer, b"> through the pipe");
+        });
+    }
+
+    // ============================================================================
+    // FIXED BUFFERS
+    // ============================================================================
+
+    #[test]
+    fn test_fixed_pool_check_out_and_return() {
+        tokio_uring::start(async {
+            let pool = FixedWaterPool::new(2, 64);
+            pool.register().unwrap();
+            let first = pool.try_check_out().unwrap();
+            let second = pool.try_check_out().unwrap();
+            assert_ne!(first.buf_index(), second.buf_index());
+            assert!(pool.try_check_out().is_none());
+            drop(first);
+            let mut again = pool.try_check_out().unwrap();
+            assert!(again.is_empty());
+            assert_eq!(again.cap(), 64);
+
+            again.extend_from_slice(b"hello world");
+            again.advance(6);
+            assert_eq!(&again[..], b"world");
+            again.extend_from_slice(&[b'!'; 58]);
+            assert_eq!(again.len(), 63);
+            assert_eq!(&again[..5], b"world");
+            again.truncate(5);
+            assert_eq!(again.to_water_buffer(), b"world");
+        });
+    }
+
+    #[test]
+    fn test_fixed_read_and_write_over_tcp() {
+        let (client, mut server) = pair();
+        server.write_all(b"ping").unwrap();
+        tokio_uring::start(async move {
+            let pool = FixedWaterPool::new(1, 16);
+            pool.register().unwrap();
+            let stream = TcpStream::from_std(client);
+
+            let mut buffer = pool.check_out().await;
+            buffer.extend_from_slice(b"> ");
+            let (result, buffer) = read_more_fixed(&stream, buffer).await;
+            assert_eq!(result.unwrap(), 4);
+            assert_eq!(&buffer[..], b"> ping");
+
+            let (result, buffer) = write_all_fixed(&stream, buffer).await;
+            result.unwrap();
+            assert!(buffer.is_empty());
+        });
+        let mut echoed = [0u8; 6];
+        server.read_exact(&mut echoed).unwrap();
+        assert_eq!(&echoed, b"> ping");
+    }
+
+    // ============================================================================
+    // PROVIDED BUFFER RING
+    // ============================================================================
+
+    #[test]
+    fn test_buf_ring_recv_and_recycle() {
+        let (client, mut server) = pair();
+        tokio_uring::start(async move {
+            let ring = WaterBufRing::new(7, 2, 8);
+            ring.provide().await.unwrap();
+            let stream = TcpStream::from_std(client);
+
+            server.write_all(b"first").unwrap();
+            let first = ring.recv(&stream).await.unwrap().unwrap();
+            assert_eq!(&first[..], b"first");
+            server.write_all(b"second").unwrap();
+            let mut second = ring.recv(&stream).await.unwrap().unwrap();
+            assert_ne!(first.buf_id(), second.buf_id());
+            second.advance(3);
+            assert_eq!(second.to_water_buffer(), b"ond");
+            assert_eq!(ring.in_use(), 2);
+
+            // every chunk is handed out, the kernel has nothing to pick from
+            server.write_all(b"third!").unwrap();
+            let error = ring.recv(&stream).await.unwrap_err();
+            assert_eq!(error.raw_os_error(), Some(libc::ENOBUFS));
+
+            let id = first.buf_id();
+            drop(first);
+            assert_eq!(ring.in_use(), 1);
+            let third = ring.recv(&stream).await.unwrap().unwrap();
+            assert_eq!(third.buf_id(), id);
+            assert_eq!(&third[..], b"third!");
+
+            drop(server);
+            drop(third);
+            assert!(ring.recv(&stream).await.unwrap().is_none());
+            drop(second);
+            ring.unregister().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_buf_ring_unregister_waits_for_reprovide() {
+        let (client, mut server) = pair();
+        tokio_uring::start(async move {
+            let ring = WaterBufRing::new(8, 1, 8);
+            ring.provide().await.unwrap();
+            let stream = TcpStream::from_std(client);
+
+            server.write_all(b"one").unwrap();
+            let chunk = ring.recv(&stream).await.unwrap().unwrap();
+            drop(chunk);
+            server.write_all(b"two").unwrap();
+            // the queued chunk is re-provided without being awaited, unregister has to wait for it
+            let chunk = ring.recv(&stream).await.unwrap().unwrap();
+            assert_eq!(&chunk[..], b"two");
+            ring.unregister().await.unwrap();
+            // dropped after unregister, the chunk is not queued again
+            drop(chunk);
+
+            // a re-provide landing after the removal would leave two chunks in the group
+            ring.provide().await.unwrap();
+            server.write_all(b"two").unwrap();
+            let chunk = ring.recv(&stream).await.unwrap().unwrap();
+            assert_eq!(&chunk[..], b"two");
+            server.write_all(b"three").unwrap();
+            let error = ring.recv(&stream).await.unwrap_err();
+            assert_eq!(error.raw_os_error(), Some(libc::ENOBUFS));
+            drop(chunk);
+            ring.unregister().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_buf_ring_unregister_discards_queued_chunks() {
+        let (client, mut server) = pair();
+        tokio_uring::start(async move {
+            let ring = WaterBufRing::new(9, 1, 8);
+            ring.provide().await.unwrap();
+            let stream = TcpStream::from_std(client);
+            server.write_all(b"queued").unwrap();
+            drop(ring.recv(&stream).await.unwrap().unwrap());
+            ring.unregister().await.unwrap();
+            assert_eq!(ring.in_use(), 0);
+        });
+    }
+
+    #[test]
+    fn test_buf_ring_drop_inside_plain_tokio_runtime() {
+        let (client, mut server) = pair();
+        let (ring, chunk) = tokio_uring::start(async move {
+            let ring = WaterBufRing::new(11, 1, 8);
+            ring.provide().await.unwrap();
+            let stream = TcpStream::from_std(client);
+            server.write_all(b"plain").unwrap();
+            let chunk = ring.recv(&stream).await.unwrap().unwrap();
+            (ring, chunk)
+        });
+        // a tokio runtime without the uring driver, submitting here would panic
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async move {
+            assert_eq!(&chunk[..], b"plain");
+            drop(chunk);
+            assert_eq!(ring.in_use(), 0);
+            drop(ring);
+        });
+    }
+
+    #[test]
+    fn test_buf_ring_drop_outside_runtime() {
+        let (client, mut server) = pair();
+        let (ring, chunk) = tokio_uring::start(async move {
+            let ring = WaterBufRing::new(10, 1, 8);
+            ring.provide().await.unwrap();
+            let stream = TcpStream::from_std(client);
+            server.write_all(b"kept").unwrap();
+            let chunk = ring.recv(&stream).await.unwrap().unwrap();
+            (ring, chunk)
+        });
+        assert_eq!(&chunk[..], b"kept");
+        // nothing can be submitted here, dropping must neither panic nor free provided memory
+        drop(chunk);
+        assert_eq!(ring.in_use(), 0);
+        drop(ring);
+    }
+
+    // ============================================================================
+    // BYTES SLICE WRAPPER
+    // ============================================================================
+
+    #[test]
+    fn test_bytes_slice_wrapper_variants() {
+        let (client, mut server) = pair();
+        tokio_uring::start(async move {
+            let stream = TcpStream::from_std(client);
+            let shared: std::sync::Arc<[u8]> = std::sync::Arc::from(&b"--shared--"[..]);
+            let mut buffer = WaterBuffer::from("xxbuffer");
+            buffer.advance(2);
+
+            let wrappers = [
+                BytesSliceWrapper::from("static "),
+                BytesSliceWrapper::shared_range(shared.clone(), 2..8),
+                BytesSliceWrapper::from(&b" "[..]),
+                BytesSliceWrapper::buffer_range(buffer, 0..3),
+            ];
+            for wrapper in wrappers {
+                let expected = wrapper.as_slice().len();
+                let (result, wrapper) = stream.write_all(wrapper).await;
+                result.unwrap();
+                assert_eq!(wrapper.as_slice().len(), expected);
+                if let Some(buffer) = wrapper.into_buffer() {
+                    assert_eq!(buffer, b"buffer");
+                }
+            }
+            // the shared handle outlives the write and still holds the bytes
+            assert_eq!(&shared[..], b"--shared--");
+        });
+        let mut out = [0u8; 17];
+        server.read_exact(&mut out).unwrap();
+        assert_eq!(&out, b"static shared buf");
+    }
+}
@@ -0,0 +1,104 @@
+#[cfg(all(test, not(feature = "circular_buffer")))]
+mod tests {
+    use super::super::super::*;
+
+    // ============================================================================
+    // ENCODE TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_put_uvarint_known_values() {
+        let mut b = WaterBuffer::with_capacity(0);
+        assert_eq!(b.put_uvarint(0), 1);
+        assert_eq!(b.put_uvarint(1), 1);
+        assert_eq!(b.put_uvarint(127), 1);
+        assert_eq!(b.put_uvarint(128), 2);
+        assert_eq!(b.put_uvarint(300), 2);
+        assert_eq!(&b[..], &[0x00, 0x01, 0x7f, 0x80, 0x01, 0xac, 0x02]);
+    }
+
+    #[test]
+    fn test_put_uvarint_max() {
+        let mut b = WaterBuffer::with_capacity(4);
+        assert_eq!(b.put_uvarint(u64::MAX), MAX_VARINT_LEN);
+        assert_eq!(&b[..9], &[0xff; 9]);
+        assert_eq!(b[9], 0x01);
+    }
+
+    #[test]
+    fn test_put_ivarint_zigzag() {
+        let mut b = WaterBuffer::with_capacity(16);
+        b.put_ivarint(0);
+        b.put_ivarint(-1);
+        b.put_ivarint(1);
+        b.put_ivarint(-2);
+        assert_eq!(&b[..], &[0, 1, 2, 3]);
+        assert_eq!(zigzag_encode(i64::MIN), u64::MAX);
+        assert_eq!(zigzag_decode(u64::MAX), i64::MIN);
+    }
+
+    #[test]
+    fn test_put_uvarint_fixed() {
+        let mut b = WaterBuffer::with_capacity(8);
+        b.put_uvarint_fixed(5, 3).unwrap();
+        assert_eq!(&b[..], &[0x85, 0x80, 0x00]);
+        assert_eq!(b.get_uvarint(), Ok(5));
+        assert_eq!(b.put_uvarint_fixed(128, 1), Err(VarintError::Overflow));
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn test_uvarint_len() {
+        assert_eq!(uvarint_len(0), 1);
+        assert_eq!(uvarint_len(127), 1);
+        assert_eq!(uvarint_len(128), 2);
+        assert_eq!(uvarint_len(u64::MAX), 10);
+    }
+
+    // ============================================================================
+    // DECODE TESTS
+    // ============================================================================
+
+    #[test]
+    fn test_round_trip() {
+        let values = [0u64, 1, 127, 128, 255, 300, 16_384, u32::MAX as u64, u64::MAX];
+        let mut b = WaterBuffer::with_capacity(8);
+        for v in values {
+            b.put_uvarint(v);
+        }
+        for v in [i64::MIN, -300, -1, 0, 1, 300, i64::MAX] {
+            b.put_ivarint(v);
+        }
+        for v in values {
+            assert_eq!(b.get_uvarint(), Ok(v));
+        }
+        for v in [i64::MIN, -300, -1, 0, 1, 300, i64::MAX] {
+            assert_eq!(b.get_ivarint(), Ok(v));
+        }
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn test_get_uvarint_truncated_consumes_nothing() {
+        let mut b = WaterBuffer::with_capacity(8);
+        b.extend_from_slice(&[0xac]);
+        assert_eq!(b.get_uvarint(), Err(VarintError::Truncated));
+        assert_eq!(b.len(), 1);
+        b.push(0x02);
+        assert_eq!(b.get_uvarint(), Ok(300));
+        assert_eq!(b.get_uvarint(), Err(VarintError::Truncated));
+    }
+
+    #[test]
+    fn test_get_uvarint_overflow() {
+        let mut b = WaterBuffer::with_capacity(16);
+        b.extend_from_slice(&[0xff; 9]);
+        b.push(0x02);
+        assert_eq!(b.get_uvarint(), Err(VarintError::Overflow));
+
+        let mut b = WaterBuffer::with_capacity(16);
+        b.extend_from_slice(&[0x80; 11]);
+        assert_eq!(b.get_uvarint(), Err(VarintError::Overflow));
+        assert_eq!(b.len(), 11);
+    }
+}
@@ -0,0 +1,222 @@
+//! Zero-copy transfers between file descriptors on Linux, enabled with the `zero-copy` feature.
+//!
+//! * [`sendfile_all`] sends a file to any descriptor, typically a socket.
+//! * [`splice_all`] moves data to or from a pipe.
+//! * [`copy_file_range_all`] copies between two files, on some filesystems without touching the data.
+//!
+//! When the kernel refuses the zero-copy call for the given descriptors (`EINVAL`, `ENOSYS`,
+//! `EXDEV`, ...) before anything was moved, every helper falls back to reading into the spare
+//! capacity of a scratch `WaterBuffer` and writing from its readable window. The returned
+//! [`Transferred`] reports the bytes moved and which path was taken.
+//! [`send_with_header`] writes header bytes from a `WaterBuffer` in front of a file body.
+//!
+//! The descriptors are expected to be blocking, `WouldBlock` is returned as an error.
+
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use crate::WaterBuffer;
+
+/// largest amount requested from the kernel in one call
+const MAX_CHUNK: usize = 1 << 30;
+
+/// scratch space reserved for the fallback copy
+const FALLBACK_CHUNK: usize = 64 * 1024;
+
+/// How the bytes were moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransferMethod {
+    /// `sendfile(2)`
+    Sendfile,
+    /// `splice(2)`
+    Splice,
+    /// `copy_file_range(2)`
+    CopyFileRange,
+    /// read into a `WaterBuffer` and written from it
+    Copy,
+}
+
+/// Outcome of a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transferred {
+    /// bytes written to the destination, fewer than requested when the source hit EOF
+    pub bytes: u64,
+    /// the path that moved them
+    pub method: TransferMethod,
+}
+
+/// Sends up to `len` bytes of `file` to `out`.
+/// Reads start at `offset`, which is advanced, or at the file position when `None`.
+pub fn sendfile_all(
+    out: &impl AsRawFd,
+    file: &impl AsRawFd,
+    offset: Option<&mut u64>,
+    len: u64,
+    scratch: &mut WaterBuffer<u8>,
+) -> io::Result<Transferred> {
+    transfer(TransferMethod::Sendfile, file.as_raw_fd(), out.as_raw_fd(), offset, len, scratch)
+}
+
+/// Moves up to `len` bytes from `from` to `to` where at least one of them is a pipe.
+/// `offset` applies to `from` and must be `None` when `from` is the pipe.
+pub fn splice_all(
+    from: &impl AsRawFd,
+    to: &impl AsRawFd,
+    offset: Option<&mut u64>,
+    len: u64,
+    scratch: &mut WaterBuffer<u8>,
+) -> io::Result<Transferred> {
+    transfer(TransferMethod::Splice, from.as_raw_fd(), to.as_raw_fd(), offset, len, scratch)
+}
+
+/// Copies up to `len` bytes from file `from` to file `to` at its current position.
+/// `offset` applies to `from`.
+pub fn copy_file_range_all(
+    from: &impl AsRawFd,
+    to: &impl AsRawFd,
+    offset: Option<&mut u64>,
+    len: u64,
+    scratch: &mut WaterBuffer<u8>,
+) -> io::Result<Transferred> {
+    transfer(TransferMethod::CopyFileRange, from.as_raw_fd(), to.as_raw_fd(), offset, len, scratch)
+}
+
+/// Writes the readable window of `header` to `out`, advancing past it, then sends the file body.
+/// The returned byte count includes the header.
+pub fn send_with_header(
+    out: &impl AsRawFd,
+    header: &mut WaterBuffer<u8>,
+    file: &impl AsRawFd,
+    offset: Option<&mut u64>,
+    len: u64,
+    scratch: &mut WaterBuffer<u8>,
+) -> io::Result<Transferred> {
+    let header_len = header.len() as u64;
+    write_all_fd(out.as_raw_fd(), header)?;
+    let mut body = sendfile_all(out, file, offset, len, scratch)?;
+    body.bytes += header_len;
+    Ok(body)
+}
+
+/// errors meaning the zero-copy call does not support these descriptors
+fn is_unsupported(error: &io::Error) -> bool {
+    matches!(
+        error.raw_os_error(),
+        Some(libc::EINVAL | libc::ENOSYS | libc::EXDEV | libc::EOPNOTSUPP)
+    )
+}
+
+/// converts a file offset for the syscalls, offsets past `off_t::MAX` are rejected
+fn to_off_t(offset: u64) -> io::Result<libc::off_t> {
+    libc::off_t::try_from(offset)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file offset does not fit into off_t"))
+}
+
+/// Runs the zero-copy call in a loop, falling back to copying when it is refused up front
+fn transfer(
+    method: TransferMethod,
+    from: RawFd,
+    to: RawFd,
+    mut offset: Option<&mut u64>,
+    len: u64,
+    scratch: &mut WaterBuffer<u8>,
+) -> io::Result<Transferred> {
+    let mut moved = 0u64;
+    while moved < len {
+        let count = (len - moved).min(MAX_CHUNK as u64) as usize;
+        let mut off = offset.as_deref().map(|&offset| to_off_t(offset)).transpose()?;
+        let off_ptr = off.as_mut().map_or(std::ptr::null_mut(), |off| off as *mut libc::off_t);
+        let n = unsafe {
+            match method {
+                TransferMethod::Sendfile => libc::sendfile(to, from, off_ptr, count),
+                TransferMethod::Splice => {
+                    libc::splice(from, off_ptr, to, std::ptr::null_mut(), count, libc::SPLICE_F_MOVE)
+                }
+                TransferMethod::CopyFileRange => {
+                    libc::copy_file_range(from, off_ptr, to, std::ptr::null_mut(), count, 0)
+                }
+                TransferMethod::Copy => unreachable!(),
+            }
+        };
+        if n < 0 {
+            let error = io::Error::last_os_error();
+            match error.kind() {
+                io::ErrorKind::Interrupted => continue,
+                _ if moved == 0 && is_unsupported(&error) => {
+                    return copy_via_buffer(from, to, offset, len, scratch);
+                }
+                _ => return Err(error),
+            }
+        }
+        if n == 0 {
+            break;
+        }
+        moved += n as u64;
+        if let Some(offset) = offset.as_deref_mut() {
+            *offset += n as u64;
+        }
+    }
+    Ok(Transferred { bytes: moved, method })
+}
+
+/// Copies up to `len` bytes through the spare capacity of `scratch`
+fn copy_via_buffer(
+    from: RawFd,
+    to: RawFd,
+    mut offset: Option<&mut u64>,
+    len: u64,
+    scratch: &mut WaterBuffer<u8>,
+) -> io::Result<Transferred> {
+    let mut moved = 0u64;
+    scratch.clear();
+    while moved < len {
+        scratch.reserve(FALLBACK_CHUNK);
+        let spare = scratch.spare_capacity_mut();
+        let count = spare.len().min((len - moved).min(MAX_CHUNK as u64) as usize);
+        let target = spare.as_mut_ptr() as *mut libc::c_void;
+        let n = match offset.as_deref() {
+            Some(&offset) => {
+                let offset = to_off_t(offset)?;
+                unsafe { libc::pread(from, target, count, offset) }
+            }
+            None => unsafe { libc::read(from, target, count) },
+        };
+        if n < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error);
+        }
+        if n == 0 {
+            break;
+        }
+        scratch.advance_mut(n as usize);
+        if let Some(offset) = offset.as_deref_mut() {
+            *offset += n as u64;
+        }
+        write_all_fd(to, scratch)?;
+        moved += n as u64;
+    }
+    Ok(Transferred { bytes: moved, method: TransferMethod::Copy })
+}
+
+/// Writes the readable window of `buffer` to `fd`, advancing past every partial write
+fn write_all_fd(fd: RawFd, buffer: &mut WaterBuffer<u8>) -> io::Result<()> {
+    while !buffer.is_empty() {
+        let chunk = buffer.chunk();
+        let n = unsafe { libc::write(fd, chunk.as_ptr() as *const libc::c_void, chunk.len()) };
+        if n < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error);
+        }
+        if n == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        buffer.advance(n as usize);
+    }
+    Ok(())
+}